@@ -0,0 +1,14 @@
+fn main() {
+    // link gtk-layer-shell through pkg-config, so a missing library fails here with a clear
+    // message rather than at link time
+    #[cfg(feature = "layer-shell")]
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("linux") {
+        if let Err(err) = pkg_config::Config::new().atleast_version("0.6").probe("gtk-layer-shell-0") {
+            panic!(
+                "the `layer-shell` feature requires gtk-layer-shell 0.6 or later, \
+                 install its development package (e.g. libgtk-layer-shell-dev or gtk-layer-shell-devel):\n{}",
+                err
+            );
+        }
+    }
+}
@@ -13,13 +13,10 @@ fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_spotlight::init(Some(tauri_plugin_spotlight::PluginConfig {
             windows: Some(vec![
-                tauri_plugin_spotlight::WindowConfig {
-                    label: String::from("main"),
-                    shortcut: String::from("Ctrl+Shift+J"),
-                    macos_window_level: None,
-                },
+                tauri_plugin_spotlight::WindowConfig::new("main", "Ctrl+Shift+J".parse().unwrap()),
             ]),
-            global_close_shortcut: Some(String::from("Escape")),
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            ..Default::default()
         })))
         .invoke_handler(tauri::generate_handler![greet])
         .setup(|app| {
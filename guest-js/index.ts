@@ -1,9 +1,152 @@
 import { invoke } from '@tauri-apps/api/tauri'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 
-export async function show () {
-  void invoke('plugin:spotlight|show')
+export type SpotlightErrorCode =
+  | 'failed_to_get_ns_window'
+  | 'failed_to_check_window_visibility'
+  | 'failed_to_hide_window'
+  | 'failed_to_show_window'
+  | 'window_not_registered'
+  | 'window_already_registered'
+  | 'window_not_found'
+  | 'invalid_shortcut'
+  | 'shortcut_conflict'
+  | 'shortcut_already_registered'
+  | 'failed_to_register_shortcut'
+  | 'failed_to_unregister_shortcut'
+  | 'command_not_allowed'
+  | 'portal_request_timed_out'
+  | 'io'
+  | 'json'
+  | 'tauri'
+  | 'rw_lock'
+  | 'mutex'
+  | 'other'
+
+export type ShortcutOwner =
+  | { kind: 'window', label: string }
+  | { kind: 'close_shortcut' }
+  | { kind: 'registered' }
+
+export interface ShortcutConflict {
+  shortcut: string
+  owners: ShortcutOwner[]
+}
+
+export interface SpotlightError {
+  code: SpotlightErrorCode
+  message: string
+  context?: {
+    label?: string
+    shortcut?: string
+    conflicts?: ShortcutConflict[]
+    command?: string
+    caller?: string
+  }
+}
+
+export type Anchor =
+  | 'top_left' | 'top' | 'top_right'
+  | 'left' | 'center' | 'right'
+  | 'bottom_left' | 'bottom' | 'bottom_right'
+
+export type Position =
+  | 'center'
+  | 'upper_third'
+  | 'cursor'
+  | 'last_position'
+  | { fixed: { anchor: Anchor, offset_x?: number, offset_y?: number } }
+
+export type MonitorSelection =
+  | 'cursor'
+  | 'focused_window'
+  | 'primary'
+  | { named: string }
+
+export interface WindowConfig {
+  label: string
+  shortcut: string
+  macos_window_level?: number
+  position?: Position
+  monitor?: MonitorSelection
+  remember_geometry?: boolean
+  min_height?: number
+  max_height?: number
+}
+
+/** Shows the spotlight window `label`, or the calling window if omitted. */
+export async function show (label?: string): Promise<void> {
+  await invoke('plugin:spotlight|show', { label })
+}
+
+/** Hides the spotlight window `label`, or the calling window if omitted. */
+export async function hide (label?: string): Promise<void> {
+  await invoke('plugin:spotlight|hide', { label })
+}
+
+/** Shows the spotlight window `label` if it is hidden and hides it otherwise. */
+export async function toggle (label?: string): Promise<void> {
+  await invoke('plugin:spotlight|toggle', { label })
+}
+
+export async function isVisible (label?: string): Promise<boolean> {
+  return await invoke<boolean>('plugin:spotlight|is_visible', { label })
+}
+
+/**
+ * Resizes the spotlight window `label`, or the calling window if omitted, to `height` logical pixels.
+ * The top edge of the window stays in place.
+ */
+export async function setContentHeight (height: number, label?: string): Promise<void> {
+  await invoke('plugin:spotlight|set_content_height', { label, height })
+}
+
+export async function register (config: WindowConfig): Promise<void> {
+  await invoke('plugin:spotlight|register', { config })
+}
+
+export async function unregister (label: string): Promise<void> {
+  await invoke('plugin:spotlight|unregister', { label })
+}
+
+export async function setShortcut (label: string, shortcut: string): Promise<void> {
+  await invoke('plugin:spotlight|set_shortcut', { label, shortcut })
+}
+
+export async function onError (handler: (error: SpotlightError) => void): Promise<UnlistenFn> {
+  return await listen<SpotlightError>('spotlight://error', (event) => { handler(event.payload) })
+}
+
+export type SpotlightEventKind = 'will_show' | 'shown' | 'will_hide' | 'hidden'
+
+export type SpotlightEventReason = 'shortcut' | 'command' | 'blur' | 'close_shortcut' | 'programmatic' | 'external'
+
+export interface SpotlightEvent {
+  kind: SpotlightEventKind
+  label: string
+  reason: SpotlightEventReason
+}
+
+async function listenSpotlightEvent (event: string, handler: (event: SpotlightEvent) => void): Promise<UnlistenFn> {
+  return await listen<SpotlightEvent>(event, (event) => { handler(event.payload) })
+}
+
+export async function onWillShow (handler: (event: SpotlightEvent) => void): Promise<UnlistenFn> {
+  return await listenSpotlightEvent('spotlight://will-show', handler)
+}
+
+export async function onShown (handler: (event: SpotlightEvent) => void): Promise<UnlistenFn> {
+  return await listenSpotlightEvent('spotlight://shown', handler)
+}
+
+export async function onWillHide (handler: (event: SpotlightEvent) => void): Promise<UnlistenFn> {
+  return await listenSpotlightEvent('spotlight://will-hide', handler)
+}
+
+export async function onHidden (handler: (event: SpotlightEvent) => void): Promise<UnlistenFn> {
+  return await listenSpotlightEvent('spotlight://hidden', handler)
 }
 
-export async function hide () {
-  void invoke('plugin:spotlight|hide')
+export async function onQuery (handler: (query: string) => void): Promise<UnlistenFn> {
+  return await listen<string>('spotlight://query', (event) => { handler(event.payload) })
 }
@@ -0,0 +1,130 @@
+//! Controls the spotlight windows of a running app over its IPC socket, e.g.
+//! `spotlight-ctl --app com.example.app toggle main --query foo`.
+
+#[cfg(unix)]
+mod client {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        os::unix::net::UnixStream,
+        path::{Path, PathBuf},
+    };
+    use tauri_plugin_spotlight::{socket_path, IpcRequest, IpcResponse};
+
+    pub const USAGE: &str = "usage: spotlight-ctl (--app <identifier> | --socket <path>) <command>
+
+commands:
+  show <label> [--query <text>]
+  hide <label>
+  toggle <label> [--query <text>]
+  status [<label>]
+  set-shortcut <label> <shortcut>";
+
+    /// Reads the socket to connect to and the request to send from the command line arguments.
+    pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<(PathBuf, IpcRequest), String> {
+        let mut socket = None;
+        let mut query = None;
+        let mut positional = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("missing value for {}", name));
+            match arg.as_str() {
+                "--app" => socket = Some(socket_path(&value("--app")?)),
+                "--socket" => socket = Some(PathBuf::from(value("--socket")?)),
+                "--query" => query = Some(value("--query")?),
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ => positional.push(arg),
+            }
+        }
+        let socket = socket.ok_or_else(|| String::from("either --app or --socket is required"))?;
+        let mut positional = positional.into_iter();
+        let command = positional.next().ok_or_else(|| String::from("missing command"))?;
+        let mut label = || positional.next().ok_or_else(|| format!("missing label for {}", command));
+        let request = match command.as_str() {
+            "show" => IpcRequest::Show { label: label()?, query },
+            "hide" => IpcRequest::Hide { label: label()? },
+            "toggle" => IpcRequest::Toggle { label: label()?, query },
+            "status" => IpcRequest::Status { label: label().ok() },
+            "set-shortcut" => {
+                let label = label()?;
+                let shortcut = positional.next().ok_or_else(|| String::from("missing shortcut"))?;
+                IpcRequest::SetShortcut { label, shortcut }
+            }
+            _ => return Err(format!("unknown command {}", command)),
+        };
+        Ok((socket, request))
+    }
+
+    /// Sends `request` and waits for the answer.
+    pub fn send(socket: &Path, request: &IpcRequest) -> Result<IpcResponse, String> {
+        let mut stream = UnixStream::connect(socket)
+            .map_err(|err| format!("failed to connect to {}: {}", socket.display(), err))?;
+        let mut line = serde_json::to_string(request).map_err(|err| err.to_string())?;
+        line.push('\n');
+        stream.write_all(line.as_bytes()).map_err(|err| err.to_string())?;
+        let mut answer = String::new();
+        BufReader::new(stream).read_line(&mut answer).map_err(|err| err.to_string())?;
+        serde_json::from_str(&answer).map_err(|err| err.to_string())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::path::PathBuf;
+        use tauri_plugin_spotlight::IpcRequest;
+        use super::parse_args;
+
+        fn args(args: &[&str]) -> Vec<String> {
+            args.iter().map(|arg| String::from(*arg)).collect()
+        }
+
+        #[test]
+        fn parse_commands() {
+            let (socket, request) = parse_args(args(&["--socket", "/tmp/app.sock", "toggle", "main", "--query", "foo"])).unwrap();
+            assert_eq!(socket, PathBuf::from("/tmp/app.sock"));
+            assert_eq!(request, IpcRequest::Toggle { label: String::from("main"), query: Some(String::from("foo")) });
+            let (_, request) = parse_args(args(&["--socket", "/tmp/app.sock", "status"])).unwrap();
+            assert_eq!(request, IpcRequest::Status { label: None });
+            let (_, request) = parse_args(args(&["--socket", "/tmp/app.sock", "set-shortcut", "main", "Ctrl+K"])).unwrap();
+            assert_eq!(request, IpcRequest::SetShortcut { label: String::from("main"), shortcut: String::from("Ctrl+K") });
+        }
+
+        #[test]
+        fn reject_incomplete_commands() {
+            assert!(parse_args(args(&["toggle", "main"])).is_err());
+            assert!(parse_args(args(&["--socket", "/tmp/app.sock", "hide"])).is_err());
+            assert!(parse_args(args(&["--socket", "/tmp/app.sock", "open", "main"])).is_err());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn main() {
+    let (socket, request) = match client::parse_args(std::env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, client::USAGE);
+            std::process::exit(2);
+        }
+    };
+    match client::send(&socket, &request) {
+        Ok(response) if response.ok => {
+            for window in response.windows.unwrap_or_default() {
+                println!("{}\t{}", window.label, window.visibility.as_str());
+            }
+        }
+        Ok(response) => {
+            let message = response.error.as_ref().and_then(|error| error["message"].as_str()).unwrap_or("request failed");
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("spotlight-ctl needs Unix domain sockets");
+    std::process::exit(1);
+}
@@ -0,0 +1,24 @@
+(function () {
+  if (typeof window.__TAURI_INVOKE__ !== 'function') {
+    return
+  }
+  window.__TAURI_INVOKE__('plugin:spotlight|close_key_binding').then(function (binding) {
+    if (!binding) {
+      return
+    }
+    window.addEventListener('keydown', function (event) {
+      if (
+        event.isComposing ||
+        event.repeat ||
+        event.code !== binding.code ||
+        event.ctrlKey !== binding.ctrlKey ||
+        event.altKey !== binding.altKey ||
+        event.shiftKey !== binding.shiftKey ||
+        event.metaKey !== binding.metaKey
+      ) {
+        return
+      }
+      window.__TAURI_INVOKE__('plugin:spotlight|close').catch(function () {})
+    }, true)
+  }).catch(function () {})
+})()
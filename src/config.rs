@@ -1,16 +1,134 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
+use crate::{CommandScope, Shortcut};
 
-#[derive(serde::Deserialize, Default, Debug, Clone, PartialEq)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
 pub struct WindowConfig {
     pub label: String,
-    pub shortcut: String,
+    pub shortcut: Shortcut,
     pub macos_window_level: Option<i32>,
+    /// Where the window is placed when shown, `Position::Center` by default
+    pub position: Option<Position>,
+    /// Which monitor the window is shown on, `MonitorSelection::Cursor` by default
+    pub monitor: Option<MonitorSelection>,
+    /// Restores the position and size the user gave the window on each monitor, instead of
+    /// applying `position`. Disabled by default.
+    pub remember_geometry: Option<bool>,
+    /// Bounds of the height set with `set_content_height`, in logical pixels
+    pub min_height: Option<f64>,
+    pub max_height: Option<f64>,
+}
+
+impl WindowConfig {
+    /// A window toggled by `shortcut`, with every option left to its default.
+    pub fn new(label: &str, shortcut: Shortcut) -> Self {
+        Self {
+            label: String::from(label),
+            shortcut,
+            macos_window_level: None,
+            position: None,
+            monitor: None,
+            remember_geometry: None,
+            min_height: None,
+            max_height: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn for_test(label: &str, shortcut: &str) -> Self {
+        Self::new(label, shortcut.parse().unwrap())
+    }
+}
+
+/// How the monitor a spotlight window is shown on is chosen. When the chosen monitor can
+/// not be found, the monitor with the cursor is used, then the primary monitor.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorSelection {
+    /// The monitor under the mouse cursor
+    Cursor,
+    /// The monitor holding the focused window of the app
+    FocusedWindow,
+    Primary,
+    /// The monitor with this name, as reported by `tauri::Monitor::name`
+    Named(String),
+}
+
+impl Default for MonitorSelection {
+    fn default() -> Self {
+        MonitorSelection::Cursor
+    }
+}
+
+/// Placement of a spotlight window on the monitor it is shown on.
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Position {
+    /// Center of the monitor
+    Center,
+    /// Centered horizontally, in the upper third of the monitor, like macOS Spotlight
+    UpperThird,
+    /// Top-left corner at the mouse cursor
+    Cursor,
+    /// Aligned to an edge or corner of the monitor, moved by logical offsets
+    Fixed {
+        anchor: Anchor,
+        #[serde(default)]
+        offset_x: f64,
+        #[serde(default)]
+        offset_y: f64,
+    },
+    /// Where the window was when it was last hidden, e.g. after being dragged, kept within
+    /// the selected monitor
+    LastPosition,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::Center
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// How `PluginConfig::global_close_shortcut` is listened for.
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseShortcutMode {
+    /// Registered as a global shortcut while a spotlight window is focused
+    Global,
+    /// Handled by a keydown listener injected into the spotlight windows
+    Local,
+}
+
+impl Default for CloseShortcutMode {
+    fn default() -> Self {
+        CloseShortcutMode::Global
+    }
 }
 
 #[derive(serde::Deserialize, Default, Debug, Clone, PartialEq)]
 pub struct PluginConfig {
     pub windows: Option<Vec<WindowConfig>>,
-    pub global_close_shortcut: Option<String>,
+    pub global_close_shortcut: Option<Shortcut>,
+    pub close_shortcut_mode: Option<CloseShortcutMode>,
+    /// Save shortcuts changed at runtime and restore them on the next launch
+    pub persist_shortcuts: Option<bool>,
+    /// Restricts which windows may invoke which commands; everything is allowed when unset
+    pub command_scopes: Option<Vec<CommandScope>>,
+    /// Accepts requests from other processes on a Unix socket, see `socket_path`
+    pub ipc_socket: Option<bool>,
 }
 
 impl PluginConfig {
@@ -21,30 +139,43 @@ impl PluginConfig {
         } else if let Some(w) = b.windows.clone() {
             windows = w;
         }
-        let mut dict: HashMap<String, String> = HashMap::default();
-        for w in &windows {
-            dict.insert(w.label.clone(), w.shortcut.clone());
-        }
+        let labels: HashSet<String> = windows.iter().map(|w| w.label.clone()).collect();
         if let Some(w) = b.windows.clone() {
             for config in w {
-                if !dict.contains_key(&config.label) {
-                    windows.push(WindowConfig {
-                        label: config.label,
-                        shortcut: config.shortcut,
-                        macos_window_level: config.macos_window_level,
-                    });
+                if !labels.contains(&config.label) {
+                    windows.push(config);
                 }
             }
         }
         Self {
             windows: {
-                if windows.len() == 0 {
+                if windows.is_empty() {
                     None
                 } else {
                     Some(windows)
                 }
             },
             global_close_shortcut: a.global_close_shortcut.clone().or(b.global_close_shortcut.clone()),
+            close_shortcut_mode: a.close_shortcut_mode.or(b.close_shortcut_mode),
+            persist_shortcuts: a.persist_shortcuts.or(b.persist_shortcuts),
+            command_scopes: a.command_scopes.clone().or(b.command_scopes.clone()),
+            ipc_socket: a.ipc_socket.or(b.ipc_socket),
+        }
+    }
+
+    /// The close shortcut to register globally, if it isn't handled in the webview.
+    pub(crate) fn grabbed_close_shortcut(&self) -> Option<&Shortcut> {
+        match self.close_shortcut_mode.unwrap_or_default() {
+            CloseShortcutMode::Global => self.global_close_shortcut.as_ref(),
+            CloseShortcutMode::Local => None,
+        }
+    }
+
+    /// The close shortcut to listen for in the webview, if it isn't registered globally.
+    pub(crate) fn local_close_shortcut(&self) -> Option<&Shortcut> {
+        match self.close_shortcut_mode.unwrap_or_default() {
+            CloseShortcutMode::Global => None,
+            CloseShortcutMode::Local => self.global_close_shortcut.as_ref(),
         }
     }
 }
@@ -53,19 +184,17 @@ impl PluginConfig {
 mod tests {
     use super::WindowConfig;
     use super::PluginConfig;
+    use super::CloseShortcutMode;
 
     #[test]
     fn merge_and_override_default_value() {
         let a = PluginConfig::default();
         let b = PluginConfig {
             windows: Some(vec![
-                WindowConfig {
-                    label: String::from("main"),
-                    shortcut: String::from("Ctrl+I"),
-                    macos_window_level: None,
-                },
+                WindowConfig::for_test("main", "Ctrl+I"),
             ]),
-            global_close_shortcut: Some(String::from("Escape")),
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            ..Default::default()
         };
         let c = PluginConfig::merge(&a, &b);
         assert_eq!(c, b);
@@ -75,39 +204,26 @@ mod tests {
     fn merge_windows() {
         let a = PluginConfig {
             windows: Some(vec![
-                WindowConfig {
-                    label: String::from("main"),
-                    shortcut: String::from("Ctrl+I"),
-                    macos_window_level: None,
-                },
+                WindowConfig::for_test("main", "Ctrl+I"),
             ]),
             global_close_shortcut: None,
+            ..Default::default()
         };
         let b = PluginConfig {
             windows: Some(vec![
-                WindowConfig {
-                    label: String::from("foo"),
-                    shortcut: String::from("bar"),
-                    macos_window_level: None,
-                },
+                WindowConfig::for_test("foo", "Alt+B"),
             ]),
             global_close_shortcut: None,
+            ..Default::default()
         };
         let c = PluginConfig::merge(&a, &b);
         assert_eq!(c, PluginConfig {
             windows: Some(vec![
-                WindowConfig {
-                    label: String::from("main"),
-                    shortcut: String::from("Ctrl+I"),
-                    macos_window_level: None,
-                },
-                WindowConfig {
-                    label: String::from("foo"),
-                    shortcut: String::from("bar"),
-                    macos_window_level: None,
-                },
+                WindowConfig::for_test("main", "Ctrl+I"),
+                WindowConfig::for_test("foo", "Alt+B"),
             ]),
             global_close_shortcut: None,
+            ..Default::default()
         });
     }
 
@@ -115,13 +231,34 @@ mod tests {
     fn a_takes_precedence_over_b() {
         let a = PluginConfig {
             windows: None,
-            global_close_shortcut: Some(String::from("Escape")),
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            close_shortcut_mode: Some(CloseShortcutMode::Local),
+            persist_shortcuts: Some(false),
+            command_scopes: Some(vec![]),
+            ipc_socket: None,
         };
         let b = PluginConfig {
             windows: None,
-            global_close_shortcut: Some(String::from("baz")),
+            global_close_shortcut: Some("Ctrl+Z".parse().unwrap()),
+            close_shortcut_mode: Some(CloseShortcutMode::Global),
+            persist_shortcuts: Some(true),
+            command_scopes: None,
+            ipc_socket: None,
         };
         let c = PluginConfig::merge(&a, &b);
         assert_eq!(c, a);
     }
+
+    #[test]
+    fn close_shortcut_mode() {
+        let mut config = PluginConfig {
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(config.grabbed_close_shortcut().is_some());
+        assert!(config.local_close_shortcut().is_none());
+        config.close_shortcut_mode = Some(CloseShortcutMode::Local);
+        assert!(config.grabbed_close_shortcut().is_none());
+        assert_eq!(config.local_close_shortcut(), config.global_close_shortcut.as_ref());
+    }
 }
@@ -0,0 +1,197 @@
+use std::fmt;
+use serde::Serialize;
+use crate::{Error, PluginConfig, Shortcut, WindowConfig};
+
+/// Something that claims a shortcut.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ShortcutOwner {
+    /// The toggle shortcut of a spotlight window
+    Window { label: String },
+    /// `PluginConfig::global_close_shortcut`
+    CloseShortcut,
+    /// Already registered with the global shortcut manager, outside of this plugin
+    Registered,
+}
+
+impl fmt::Display for ShortcutOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShortcutOwner::Window { label } => write!(f, "window \"{}\"", label),
+            ShortcutOwner::CloseShortcut => write!(f, "global close shortcut"),
+            ShortcutOwner::Registered => write!(f, "another global shortcut"),
+        }
+    }
+}
+
+/// A shortcut claimed by more than one owner.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ShortcutConflict {
+    pub shortcut: Shortcut,
+    pub owners: Vec<ShortcutOwner>,
+}
+
+impl fmt::Display for ShortcutConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let owners: Vec<String> = self.owners.iter().map(ShortcutOwner::to_string).collect();
+        write!(f, "{} is used by {}", self.shortcut, owners.join(", "))
+    }
+}
+
+/// Formats a conflict report for error messages.
+pub(crate) fn describe_conflicts(conflicts: &[ShortcutConflict]) -> String {
+    let conflicts: Vec<String> = conflicts.iter().map(ShortcutConflict::to_string).collect();
+    conflicts.join("; ")
+}
+
+impl PluginConfig {
+    /// Returns the shortcuts that are claimed by several windows or by a window and
+    /// the close shortcut, taking `CommandOrControl` into account.
+    ///
+    /// `is_registered` is asked whether a shortcut is already taken outside of the plugin.
+    pub fn find_conflicts<F: Fn(&Shortcut) -> bool>(&self, is_registered: F) -> Vec<ShortcutConflict> {
+        let mut claims: Vec<(Shortcut, ShortcutOwner)> = vec![];
+        if let Some(windows) = &self.windows {
+            for window in windows {
+                claims.push((window.shortcut.clone(), ShortcutOwner::Window { label: window.label.clone() }));
+            }
+        }
+        if let Some(close_shortcut) = &self.global_close_shortcut {
+            claims.push((close_shortcut.clone(), ShortcutOwner::CloseShortcut));
+        }
+
+        let mut groups: Vec<ShortcutConflict> = vec![];
+        for (shortcut, owner) in claims {
+            match groups.iter_mut().find(|group| group.shortcut.conflicts_with(&shortcut)) {
+                Some(group) => group.owners.push(owner),
+                None => groups.push(ShortcutConflict { shortcut, owners: vec![owner] }),
+            }
+        }
+        for group in groups.iter_mut() {
+            if is_registered(&group.shortcut) {
+                group.owners.push(ShortcutOwner::Registered);
+            }
+        }
+        groups.retain(|group| group.owners.len() > 1);
+        groups
+    }
+}
+
+/// Checks `window_config` against the registered windows, the configured windows that are not
+/// registered yet and the close shortcut.
+pub(crate) fn check_window_conflicts<'a, I>(config: &PluginConfig, registered: I, window_config: &WindowConfig) -> Result<(), Error>
+where
+    I: Iterator<Item = &'a WindowConfig>,
+{
+    let registered: Vec<&WindowConfig> = registered.collect();
+    // a registered window may have been rebound since it was configured
+    let configured = config
+        .windows
+        .iter()
+        .flatten()
+        .filter(|configured| !registered.iter().any(|registered| registered.label == configured.label));
+    let mut windows: Vec<WindowConfig> = registered
+        .iter()
+        .copied()
+        .chain(configured)
+        .filter(|window| window.label != window_config.label)
+        .cloned()
+        .collect();
+    windows.push(window_config.clone());
+    let config = PluginConfig {
+        windows: Some(windows),
+        global_close_shortcut: config.global_close_shortcut.clone(),
+        ..Default::default()
+    };
+    let owner = ShortcutOwner::Window { label: window_config.label.clone() };
+    let conflicts: Vec<ShortcutConflict> = config
+        .find_conflicts(|_| false)
+        .into_iter()
+        .filter(|conflict| conflict.owners.contains(&owner))
+        .collect();
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::ShortcutConflict(conflicts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_window_conflicts, ShortcutConflict, ShortcutOwner};
+    use crate::{Error, PluginConfig, WindowConfig};
+
+    fn window(label: &str, shortcut: &str) -> WindowConfig {
+        WindowConfig::for_test(label, shortcut)
+    }
+
+    #[test]
+    fn no_conflicts() {
+        let config = PluginConfig {
+            windows: Some(vec![window("main", "Ctrl+I"), window("foo", "Ctrl+Shift+I")]),
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(config.find_conflicts(|_| false).is_empty());
+    }
+
+    #[test]
+    fn duplicate_window_shortcuts() {
+        let config = PluginConfig {
+            windows: Some(vec![window("main", "Ctrl+I"), window("foo", "Control+i"), window("bar", "Alt+I")]),
+            ..Default::default()
+        };
+        assert_eq!(config.find_conflicts(|_| false), vec![ShortcutConflict {
+            shortcut: "Ctrl+I".parse().unwrap(),
+            owners: vec![
+                ShortcutOwner::Window { label: String::from("main") },
+                ShortcutOwner::Window { label: String::from("foo") },
+            ],
+        }]);
+    }
+
+    #[test]
+    fn window_shortcut_matches_close_shortcut() {
+        let config = PluginConfig {
+            windows: Some(vec![window("main", "Esc")]),
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            ..Default::default()
+        };
+        let conflicts = config.find_conflicts(|_| false);
+        assert_eq!(conflicts[0].owners, vec![
+            ShortcutOwner::Window { label: String::from("main") },
+            ShortcutOwner::CloseShortcut,
+        ]);
+        assert_eq!(conflicts[0].to_string(), "Escape is used by window \"main\", global close shortcut");
+    }
+
+    #[test]
+    fn shortcut_already_registered() {
+        let config = PluginConfig {
+            windows: Some(vec![window("main", "Ctrl+I")]),
+            ..Default::default()
+        };
+        let conflicts = config.find_conflicts(|shortcut| shortcut.to_string() == "Control+I");
+        assert_eq!(conflicts[0].owners, vec![
+            ShortcutOwner::Window { label: String::from("main") },
+            ShortcutOwner::Registered,
+        ]);
+    }
+
+    #[test]
+    fn window_conflicts_with_unregistered_configured_window() {
+        let config = PluginConfig {
+            windows: Some(vec![window("main", "Ctrl+I")]),
+            ..Default::default()
+        };
+        let err = check_window_conflicts(&config, std::iter::empty(), &window("foo", "Ctrl+I")).unwrap_err();
+        assert!(matches!(err, Error::ShortcutConflict(conflicts) if conflicts[0].owners == vec![
+            ShortcutOwner::Window { label: String::from("main") },
+            ShortcutOwner::Window { label: String::from("foo") },
+        ]));
+
+        // the registered shortcut of a configured window replaces the configured one
+        let main = window("main", "Ctrl+K");
+        assert!(check_window_conflicts(&config, std::iter::once(&main), &window("foo", "Ctrl+I")).is_ok());
+    }
+}
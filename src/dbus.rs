@@ -0,0 +1,277 @@
+use std::sync::{Arc, Mutex};
+use gio::{glib, prelude::*, BusNameOwnerFlags, BusType, DBusConnection, DBusMethodInvocation, DBusNodeInfo, RegistrationId};
+use tauri::{AppHandle, Manager, Runtime};
+use crate::{Error, Reason, SpotlightEventKind, SpotlightManager};
+use crate::error::report_error;
+use crate::visibility::Request;
+
+pub const INTERFACE_NAME: &str = "app.tauri.Spotlight";
+pub const OBJECT_PATH: &str = "/app/tauri/Spotlight";
+
+const INTROSPECTION_XML: &str = r#"
+<node>
+  <interface name="app.tauri.Spotlight">
+    <method name="Show">
+      <arg name="label" type="s" direction="in"/>
+    </method>
+    <method name="Hide">
+      <arg name="label" type="s" direction="in"/>
+    </method>
+    <method name="Toggle">
+      <arg name="label" type="s" direction="in"/>
+    </method>
+    <method name="ListWindows">
+      <arg name="windows" type="a(sb)" direction="out"/>
+    </method>
+    <signal name="VisibilityChanged">
+      <arg name="label" type="s"/>
+      <arg name="visible" type="b"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+/// Name owned on the session bus, e.g. `com.example.app.Spotlight` for an app identified
+/// as `com.example.app`.
+pub(crate) fn bus_name<R: Runtime>(app_handle: &AppHandle<R>) -> String {
+    format!("{}.Spotlight", app_handle.config().tauri.bundle.identifier)
+}
+
+/// Connection the spotlight interface is currently exported on.
+pub(crate) type ExportedConnection = Arc<Mutex<Option<DBusConnection>>>;
+
+/// Owns the bus name of the app on the session bus and exports the spotlight interface
+/// once connected. Method calls are handled on the main thread, like shortcuts.
+pub(crate) fn start<R: Runtime>(app_handle: AppHandle<R>) {
+    let name = bus_name(&app_handle);
+    // gio aborts on invalid names, e.g. an identifier with an element starting with a digit
+    if !gio::dbus_is_name(&name) || gio::dbus_is_unique_name(&name) {
+        report_error(&app_handle, Error::Other(format!("{} is not a valid D-Bus name", name)));
+        return;
+    }
+    let exported = ExportedConnection::default();
+    emit_visibility_changes(&app_handle, exported.clone());
+    let lost = exported.clone();
+    let error_handle = app_handle.clone();
+    // the name is owned for the lifetime of the app
+    let _ = gio::bus_own_name(
+        BusType::Session,
+        &name,
+        BusNameOwnerFlags::NONE,
+        move |connection, _| match export(&app_handle, &connection) {
+            Ok(_) => set_connection(&exported, Some(connection)),
+            Err(err) => report_error(&app_handle, err),
+        },
+        |_, _| {},
+        move |_, name| {
+            set_connection(&lost, None);
+            let err = Error::Other(format!("failed to own D-Bus name {}", name));
+            report_error(&error_handle, err);
+        },
+    );
+}
+
+fn set_connection(exported: &ExportedConnection, connection: Option<DBusConnection>) {
+    if let Ok(mut exported) = exported.lock() {
+        *exported = connection;
+    }
+}
+
+/// Exports the spotlight interface on `connection`.
+pub fn export<R: Runtime>(app_handle: &AppHandle<R>, connection: &DBusConnection) -> Result<RegistrationId, Error> {
+    let interface_info = DBusNodeInfo::for_xml(INTROSPECTION_XML)
+        .map_err(|err| Error::Other(err.to_string()))?
+        .lookup_interface(INTERFACE_NAME)
+        .ok_or_else(|| Error::Other(format!("missing D-Bus interface {}", INTERFACE_NAME)))?;
+    let handle = app_handle.clone();
+    connection
+        .register_object(
+            OBJECT_PATH,
+            &interface_info,
+            move |_, _, _, _, method_name, parameters, invocation| {
+                handle_method_call(&handle, method_name, parameters, invocation);
+            },
+            |_, _, _, _, _| ().to_variant(),
+            |_, _, _, _, _, _| false,
+        )
+        .map_err(|err| Error::Other(err.to_string()))
+}
+
+/// Emits `VisibilityChanged` on the exported connection whenever a spotlight window is shown
+/// or hidden. The listener is registered once, so exporting the interface again after the
+/// name was lost doesn't emit each signal twice.
+pub(crate) fn emit_visibility_changes<R: Runtime>(app_handle: &AppHandle<R>, exported: ExportedConnection) {
+    let error_handle = app_handle.clone();
+    app_handle.state::<SpotlightManager>().on_event(move |event| {
+        let visible = match event.kind {
+            SpotlightEventKind::Shown => true,
+            SpotlightEventKind::Hidden => false,
+            _ => return,
+        };
+        let connection = match exported.lock() {
+            Ok(connection) => connection.clone(),
+            Err(_) => return,
+        };
+        let connection = match connection {
+            Some(connection) => connection,
+            None => return,
+        };
+        let parameters = (event.label.as_str(), visible).to_variant();
+        if let Err(err) = connection.emit_signal(None, OBJECT_PATH, INTERFACE_NAME, "VisibilityChanged", Some(&parameters)) {
+            report_error(&error_handle, Error::Other(err.to_string()));
+        }
+    });
+}
+
+fn handle_method_call<R: Runtime>(app_handle: &AppHandle<R>, method_name: &str, parameters: glib::Variant, invocation: DBusMethodInvocation) {
+    let request = match method_name {
+        "Show" => Request::Show,
+        "Hide" => Request::Hide,
+        "Toggle" => Request::Toggle,
+        "ListWindows" => {
+            return match list_windows(app_handle) {
+                Ok(windows) => invocation.return_value(Some(&(windows,).to_variant())),
+                Err(err) => return_error(invocation, err),
+            };
+        }
+        _ => {
+            let message = format!("unknown method {}", method_name);
+            return invocation.return_dbus_error("org.freedesktop.DBus.Error.UnknownMethod", &message);
+        }
+    };
+    let label = match parameters.get::<(String,)>() {
+        Some((label,)) => label,
+        None => return invocation.return_dbus_error("org.freedesktop.DBus.Error.InvalidArgs", "expected a window label"),
+    };
+    match request_window(app_handle, &label, request) {
+        Ok(()) => invocation.return_value(None),
+        Err(err) => return_error(invocation, err),
+    }
+}
+
+fn request_window<R: Runtime>(app_handle: &AppHandle<R>, label: &str, request: Request) -> Result<(), Error> {
+    let window = app_handle
+        .get_window(label)
+        .ok_or_else(|| Error::WindowNotFound { label: String::from(label) })?;
+    app_handle.state::<SpotlightManager>().request(&window, request, Reason::External)
+}
+
+/// Returns the label and visibility of every registered window.
+fn list_windows<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Vec<(String, bool)>, Error> {
+    let manager = app_handle.state::<SpotlightManager>();
+    let mut windows = Vec::new();
+    for label in manager.registered_windows()? {
+        let visible = manager.visibility(&label)?.is_visible();
+        windows.push((label, visible));
+    }
+    Ok(windows)
+}
+
+/// Replies with a D-Bus error named after the error code, e.g.
+/// `app.tauri.Spotlight.Error.window_not_registered`.
+fn return_error(invocation: DBusMethodInvocation, err: Error) {
+    let name = format!("{}.Error.{}", INTERFACE_NAME, err.code());
+    invocation.return_dbus_error(&name, &err.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread, time::Duration};
+    use gio::{glib, prelude::*, Cancellable, DBusCallFlags, DBusConnection, DBusSignalFlags};
+    use tauri::test::{mock_builder, mock_context, noop_assets};
+    use crate::{PluginConfig, WindowConfig};
+    use crate::test_bus::PrivateBus;
+    use super::{emit_visibility_changes, export, ExportedConnection, INTERFACE_NAME, OBJECT_PATH};
+
+    fn call(connection: &DBusConnection, bus_name: &str, method: &str, parameters: Option<glib::Variant>) -> Result<glib::Variant, glib::Error> {
+        connection.call_sync(
+            Some(bus_name),
+            OBJECT_PATH,
+            INTERFACE_NAME,
+            method,
+            parameters.as_ref(),
+            None,
+            DBusCallFlags::NONE,
+            1000,
+            None::<&Cancellable>,
+        )
+    }
+
+    #[test]
+    fn control_windows_over_dbus() {
+        let bus = PrivateBus::spawn();
+        let app = mock_builder()
+            .plugin(crate::init(Some(PluginConfig {
+                windows: Some(vec![WindowConfig::for_test("main", "Ctrl+Shift+J")]),
+                ..Default::default()
+            })))
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        // method calls are dispatched to the context the object was exported from
+        let context = glib::MainContext::new();
+        let exported = ExportedConnection::default();
+        emit_visibility_changes(&app.handle(), exported.clone());
+        let server = context
+            .with_thread_default(|| {
+                // the interface is exported again after the name is lost and acquired back
+                let lost = bus.connect();
+                let registration_id = export(&app.handle(), &lost).unwrap();
+                *exported.lock().unwrap() = Some(lost.clone());
+                lost.unregister_object(registration_id).unwrap();
+                let connection = bus.connect();
+                export(&app.handle(), &connection).unwrap();
+                *exported.lock().unwrap() = Some(connection.clone());
+                connection
+            })
+            .unwrap();
+        let bus_name = server.unique_name().unwrap().to_string();
+        let client = bus.connect();
+        let (signal_tx, signal_rx) = mpsc::channel();
+        // signals from any connection, so a listener left on the lost one would show up
+        client.signal_subscribe(
+            None,
+            Some(INTERFACE_NAME),
+            Some("VisibilityChanged"),
+            Some(OBJECT_PATH),
+            None,
+            DBusSignalFlags::NONE,
+            move |_, _, _, _, _, parameters| {
+                let _ = signal_tx.send(parameters.get::<(String, bool)>());
+            },
+        );
+
+        let (result_tx, result_rx) = mpsc::channel();
+        let caller = thread::spawn(move || {
+            call(&client, &bus_name, "Hide", Some((String::from("main"),).to_variant())).unwrap();
+            let windows = call(&client, &bus_name, "ListWindows", None).unwrap();
+            let err = call(&client, &bus_name, "Show", Some((String::from("settings"),).to_variant())).unwrap_err();
+            let _ = result_tx.send((windows.get::<(Vec<(String, bool)>,)>(), err.to_string()));
+            // keeps the client alive until the signal is received
+            client
+        });
+        let (windows, err) = loop {
+            context.iteration(false);
+            if let Ok(result) = result_rx.try_recv() {
+                break result;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        let client = caller.join().unwrap();
+
+        assert_eq!(windows, Some((vec![(String::from("main"), false)],)));
+        assert!(err.contains("app.tauri.Spotlight.Error.window_not_found"));
+        // signals are dispatched to the default context of the thread that subscribed
+        let signal = loop {
+            glib::MainContext::default().iteration(false);
+            if let Ok(signal) = signal_rx.recv_timeout(Duration::from_millis(10)) {
+                break signal;
+            }
+        };
+        assert_eq!(signal, Some((String::from("main"), false)));
+        for _ in 0..10 {
+            glib::MainContext::default().iteration(false);
+        }
+        assert!(signal_rx.recv_timeout(Duration::from_millis(50)).is_err());
+        drop(client);
+    }
+}
@@ -1,13 +1,46 @@
+use std::{fmt, sync::Mutex};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use tauri::{AppHandle, Manager, Runtime};
+use crate::{ShortcutConflict, ShortcutParseError, SpotlightManager};
+use crate::conflict::describe_conflicts;
+
+/// Event emitted to the frontend whenever the plugin fails in a background callback.
+pub const ERROR_EVENT: &str = "spotlight://error";
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("failed to get NSWindow")]
     FailedToGetNSWindow,
-    #[error("failed to get NSObject class")]
-    FailedToCheckWindowVisibility,
-    #[error("failed to hide window")]
-    FailedToHideWindow,
-    #[error("failed to show window")]
-    FailedToShowWindow,
+    #[error("failed to check visibility of window: {label}")]
+    FailedToCheckWindowVisibility { label: String },
+    #[error("failed to hide window: {label}")]
+    FailedToHideWindow { label: String },
+    #[error("failed to show window: {label}")]
+    FailedToShowWindow { label: String },
+    #[error("window is not registered: {label}")]
+    WindowNotRegistered { label: String },
+    #[error("window is already registered: {label}")]
+    WindowAlreadyRegistered { label: String },
+    #[error("window not found: {label}")]
+    WindowNotFound { label: String },
+    #[error("invalid shortcut: {0}")]
+    InvalidShortcut(#[from] ShortcutParseError),
+    #[error("conflicting shortcuts: {}", describe_conflicts(.0))]
+    ShortcutConflict(Vec<ShortcutConflict>),
+    #[error("shortcut is already registered: {shortcut}")]
+    ShortcutAlreadyRegistered { shortcut: String },
+    #[error("failed to register shortcut: {shortcut}")]
+    FailedToRegisterShortcut { shortcut: String },
+    #[error("failed to unregister shortcut: {shortcut}")]
+    FailedToUnregisterShortcut { shortcut: String },
+    #[error("window \"{caller}\" is not allowed to invoke {command} on window \"{target}\"")]
+    CommandNotAllowed { command: String, caller: String, target: String },
+    #[error("the desktop portal did not answer {method} in time")]
+    PortalRequestTimedOut { method: String },
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
     #[error("tauri err: {0}")]
     Tauri(#[from] tauri::Error),
     #[error("rwLock: {0}")]
@@ -17,3 +50,168 @@ pub enum Error {
     #[error("other: {0}")]
     Other(String),
 }
+
+/// Additional information attached to a serialized [`Error`].
+#[derive(Serialize, Default, Debug, Clone, PartialEq)]
+pub struct ErrorContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shortcut: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicts: Option<Vec<ShortcutConflict>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Label of the window that invoked the command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caller: Option<String>,
+}
+
+impl Error {
+    /// Stable, machine-readable identifier of the error kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::FailedToGetNSWindow => "failed_to_get_ns_window",
+            Error::FailedToCheckWindowVisibility { .. } => "failed_to_check_window_visibility",
+            Error::FailedToHideWindow { .. } => "failed_to_hide_window",
+            Error::FailedToShowWindow { .. } => "failed_to_show_window",
+            Error::WindowNotRegistered { .. } => "window_not_registered",
+            Error::WindowAlreadyRegistered { .. } => "window_already_registered",
+            Error::WindowNotFound { .. } => "window_not_found",
+            Error::InvalidShortcut(_) => "invalid_shortcut",
+            Error::ShortcutConflict(_) => "shortcut_conflict",
+            Error::ShortcutAlreadyRegistered { .. } => "shortcut_already_registered",
+            Error::FailedToRegisterShortcut { .. } => "failed_to_register_shortcut",
+            Error::FailedToUnregisterShortcut { .. } => "failed_to_unregister_shortcut",
+            Error::CommandNotAllowed { .. } => "command_not_allowed",
+            Error::PortalRequestTimedOut { .. } => "portal_request_timed_out",
+            Error::Io(_) => "io",
+            Error::Json(_) => "json",
+            Error::Tauri(_) => "tauri",
+            Error::RwLock(_) => "rw_lock",
+            Error::Mutex(_) => "mutex",
+            Error::Other(_) => "other",
+        }
+    }
+
+    pub fn context(&self) -> Option<ErrorContext> {
+        match self {
+            Error::FailedToCheckWindowVisibility { label }
+            | Error::FailedToHideWindow { label }
+            | Error::FailedToShowWindow { label }
+            | Error::WindowNotRegistered { label }
+            | Error::WindowAlreadyRegistered { label }
+            | Error::WindowNotFound { label } => Some(ErrorContext {
+                label: Some(label.clone()),
+                ..Default::default()
+            }),
+            Error::ShortcutAlreadyRegistered { shortcut }
+            | Error::FailedToRegisterShortcut { shortcut }
+            | Error::FailedToUnregisterShortcut { shortcut } => Some(ErrorContext {
+                shortcut: Some(shortcut.clone()),
+                ..Default::default()
+            }),
+            Error::InvalidShortcut(err) => Some(ErrorContext {
+                shortcut: Some(String::from(err.shortcut())),
+                ..Default::default()
+            }),
+            Error::ShortcutConflict(conflicts) => Some(ErrorContext {
+                conflicts: Some(conflicts.clone()),
+                ..Default::default()
+            }),
+            Error::CommandNotAllowed { command, caller, target } => Some(ErrorContext {
+                label: Some(target.clone()),
+                command: Some(command.clone()),
+                caller: Some(caller.clone()),
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let context = self.context();
+        let mut state = serializer.serialize_struct("Error", if context.is_some() { 3 } else { 2 })?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(context) = context {
+            state.serialize_field("context", &context)?;
+        } else {
+            state.skip_field("context")?;
+        }
+        state.end()
+    }
+}
+
+type BoxedErrorHandler = Box<dyn Fn(&Error) + Send + Sync>;
+
+/// Holds the user-installed callback for errors that can not be returned to a caller,
+/// e.g. failures inside shortcut or window event callbacks.
+#[derive(Default)]
+pub(crate) struct ErrorHandler(Mutex<Option<BoxedErrorHandler>>);
+
+impl ErrorHandler {
+    pub(crate) fn set<F: Fn(&Error) + Send + Sync + 'static>(&self, handler: F) {
+        if let Ok(mut guard) = self.0.lock() {
+            guard.replace(Box::new(handler));
+        }
+    }
+
+    fn call(&self, err: &Error) {
+        if let Ok(guard) = self.0.lock() {
+            if let Some(handler) = guard.as_ref() {
+                handler(err);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ErrorHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ErrorHandler")
+    }
+}
+
+/// Passes the error to the installed error handler and emits it to the frontend.
+pub(crate) fn report_error<R: Runtime>(app_handle: &AppHandle<R>, err: Error) {
+    if let Some(manager) = app_handle.try_state::<SpotlightManager>() {
+        manager.error_handler.call(&err);
+    }
+    let _ = app_handle.emit_all(ERROR_EVENT, &err);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    #[test]
+    fn serialize_error_with_context() {
+        let err = Error::WindowNotRegistered { label: String::from("main") };
+        assert_eq!(serde_json::to_value(err).unwrap(), serde_json::json!({
+            "code": "window_not_registered",
+            "message": "window is not registered: main",
+            "context": { "label": "main" },
+        }));
+    }
+
+    #[test]
+    fn serialize_shortcut_error() {
+        let err = Error::ShortcutAlreadyRegistered { shortcut: String::from("Ctrl+I") };
+        assert_eq!(serde_json::to_value(err).unwrap(), serde_json::json!({
+            "code": "shortcut_already_registered",
+            "message": "shortcut is already registered: Ctrl+I",
+            "context": { "shortcut": "Ctrl+I" },
+        }));
+    }
+
+    #[test]
+    fn serialize_error_without_context() {
+        let err = Error::Other(String::from("foo"));
+        assert_eq!(serde_json::to_value(err).unwrap(), serde_json::json!({
+            "code": "other",
+            "message": "other: foo",
+        }));
+    }
+}
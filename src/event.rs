@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{atomic::{AtomicU64, Ordering}, mpsc::{channel, Receiver, Sender}, Arc, Mutex},
+    time::Duration,
+};
+use serde::Serialize;
+use tauri::{Manager, Runtime, Window};
+
+pub const WILL_SHOW_EVENT: &str = "spotlight://will-show";
+pub const SHOWN_EVENT: &str = "spotlight://shown";
+pub const WILL_HIDE_EVENT: &str = "spotlight://will-hide";
+pub const HIDDEN_EVENT: &str = "spotlight://hidden";
+/// Sent to a window with the query passed along an IPC request to show it
+pub const QUERY_EVENT: &str = "spotlight://query";
+
+/// A step of a spotlight window being shown or hidden.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpotlightEventKind {
+    WillShow,
+    Shown,
+    WillHide,
+    Hidden,
+}
+
+impl SpotlightEventKind {
+    /// Name of the event emitted to the frontend.
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            SpotlightEventKind::WillShow => WILL_SHOW_EVENT,
+            SpotlightEventKind::Shown => SHOWN_EVENT,
+            SpotlightEventKind::WillHide => WILL_HIDE_EVENT,
+            SpotlightEventKind::Hidden => HIDDEN_EVENT,
+        }
+    }
+}
+
+/// What caused a spotlight window to be shown or hidden.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    /// The toggle shortcut of the window
+    Shortcut,
+    /// A command invoked from the frontend
+    Command,
+    /// The window lost focus
+    Blur,
+    /// The close shortcut
+    CloseShortcut,
+    /// A call to the `SpotlightManager` from Rust
+    Programmatic,
+    /// A request from another process, over D-Bus or the IPC socket
+    External,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SpotlightEvent {
+    pub kind: SpotlightEventKind,
+    pub label: String,
+    pub reason: Reason,
+}
+
+type BoxedEventListener = Arc<dyn Fn(&SpotlightEvent) + Send + Sync>;
+
+/// Holds the callbacks installed with `SpotlightManager::on_event`.
+#[derive(Default)]
+pub(crate) struct EventListeners(Mutex<Vec<BoxedEventListener>>);
+
+impl EventListeners {
+    pub(crate) fn add<F: Fn(&SpotlightEvent) + Send + Sync + 'static>(&self, listener: F) {
+        if let Ok(mut guard) = self.0.lock() {
+            guard.push(Arc::new(listener));
+        }
+    }
+
+    /// Calls the listeners on a copy of the list, so that a listener may add another one.
+    fn call(&self, event: &SpotlightEvent) {
+        let listeners = match self.0.lock() {
+            Ok(guard) => guard.clone(),
+            Err(_) => return,
+        };
+        for listener in listeners {
+            listener(event);
+        }
+    }
+
+    /// Passes the event to the listeners and emits it to every window.
+    pub(crate) fn emit<R: Runtime>(&self, window: &Window<R>, kind: SpotlightEventKind, reason: Reason) {
+        let event = SpotlightEvent {
+            kind,
+            label: window.label().to_string(),
+            reason,
+        };
+        self.call(&event);
+        let _ = window.emit_all(kind.event_name(), &event);
+    }
+}
+
+impl fmt::Debug for EventListeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EventListeners")
+    }
+}
+
+type BoxedHook = Arc<dyn Fn(&str, Reason) -> bool + Send + Sync>;
+
+/// Holds the callbacks installed with `SpotlightManager::before_show` or `before_hide`.
+#[derive(Default)]
+pub(crate) struct Hooks(Mutex<Vec<BoxedHook>>);
+
+impl Hooks {
+    pub(crate) fn add<F: Fn(&str, Reason) -> bool + Send + Sync + 'static>(&self, hook: F) {
+        if let Ok(mut guard) = self.0.lock() {
+            guard.push(Arc::new(hook));
+        }
+    }
+
+    /// Whether every hook allows the transition of window `label`.
+    pub(crate) fn allow(&self, label: &str, reason: Reason) -> bool {
+        let hooks = match self.0.lock() {
+            Ok(guard) => guard.clone(),
+            Err(_) => return true,
+        };
+        hooks.iter().all(|hook| hook(label, reason))
+    }
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hooks")
+    }
+}
+
+/// How long a window waits for its webview to handle `spotlight://will-show` before being shown.
+pub(crate) const WILL_SHOW_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Asks the webview to acknowledge `spotlight://will-show` with `sequence`, see `will_show.js`.
+pub(crate) fn will_show_ack_script(sequence: u64) -> String {
+    format!("window.__SPOTLIGHT_WILL_SHOW__ && window.__SPOTLIGHT_WILL_SHOW__({})", sequence)
+}
+
+/// Tracks the webviews acknowledging `spotlight://will-show`, so that their window is only
+/// shown once the frontend had a chance to update its content.
+#[derive(Default, Debug)]
+pub(crate) struct WillShowAcks {
+    /// Labels of the windows whose webview loaded the injected script
+    ready: Mutex<HashSet<String>>,
+    /// The show each window waits for, by the sequence number its webview echoes
+    pending: Mutex<HashMap<String, (u64, Sender<()>)>>,
+    sequence: AtomicU64,
+}
+
+impl WillShowAcks {
+    /// Returns the sequence number and a receiver for the acknowledgement of the next show of
+    /// window `label`, or `None` if its webview does not acknowledge the event.
+    pub(crate) fn expect(&self, label: &str) -> Option<(u64, Receiver<()>)> {
+        if !self.ready.lock().ok()?.contains(label) {
+            return None;
+        }
+        let sequence = self.sequence.fetch_add(1, Ordering::SeqCst) + 1;
+        let (tx, rx) = channel();
+        self.pending.lock().ok()?.insert(String::from(label), (sequence, tx));
+        Some((sequence, rx))
+    }
+
+    /// Records an acknowledgement from window `label`. Without a sequence number it only marks
+    /// its webview as ready, it is sent when the page loads. A late acknowledgement of an
+    /// earlier show is ignored.
+    pub(crate) fn acknowledge(&self, label: &str, sequence: Option<u64>) {
+        let sequence = match sequence {
+            Some(sequence) => sequence,
+            None => {
+                if let Ok(mut ready) = self.ready.lock() {
+                    ready.insert(String::from(label));
+                }
+                return;
+            }
+        };
+        let pending = self.pending.lock().ok().and_then(|mut pending| match pending.get(label) {
+            Some((expected, _)) if *expected == sequence => pending.remove(label),
+            _ => None,
+        });
+        if let Some((_, tx)) = pending {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Forgets the webview of window `label`, once it is destroyed or loads another page. A
+    /// show waiting for it goes on right away.
+    pub(crate) fn forget(&self, label: &str) {
+        if let Ok(mut ready) = self.ready.lock() {
+            ready.remove(label);
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(label);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hooks, Reason, SpotlightEvent, SpotlightEventKind, WillShowAcks};
+
+    #[test]
+    fn serialize_event() {
+        let event = SpotlightEvent {
+            kind: SpotlightEventKind::WillHide,
+            label: String::from("main"),
+            reason: Reason::CloseShortcut,
+        };
+        assert_eq!(serde_json::to_value(event).unwrap(), serde_json::json!({
+            "kind": "will_hide",
+            "label": "main",
+            "reason": "close_shortcut",
+        }));
+    }
+
+    #[test]
+    fn hooks_deny_when_any_hook_denies() {
+        let hooks = Hooks::default();
+        assert!(hooks.allow("main", Reason::Shortcut));
+        hooks.add(|_, _| true);
+        hooks.add(|label, reason| label != "main" || reason != Reason::Blur);
+        assert!(hooks.allow("main", Reason::Shortcut));
+        assert!(!hooks.allow("main", Reason::Blur));
+    }
+
+    #[test]
+    fn wait_for_ready_webviews_only() {
+        let acks = WillShowAcks::default();
+        assert!(acks.expect("main").is_none());
+        acks.acknowledge("main", None);
+        let (sequence, ack) = acks.expect("main").unwrap();
+        assert!(ack.try_recv().is_err());
+        acks.acknowledge("main", Some(sequence));
+        assert!(ack.try_recv().is_ok());
+        assert!(acks.expect("settings").is_none());
+    }
+
+    #[test]
+    fn ignore_late_acknowledgements() {
+        let acks = WillShowAcks::default();
+        acks.acknowledge("main", None);
+        let (earlier, _) = acks.expect("main").unwrap();
+        let (sequence, ack) = acks.expect("main").unwrap();
+        acks.acknowledge("main", Some(earlier));
+        assert!(ack.try_recv().is_err());
+        acks.acknowledge("main", Some(sequence));
+        assert!(ack.try_recv().is_ok());
+    }
+
+    #[test]
+    fn forget_reloaded_webviews() {
+        let acks = WillShowAcks::default();
+        acks.acknowledge("main", None);
+        let (_, ack) = acks.expect("main").unwrap();
+        acks.forget("main");
+        assert!(ack.try_recv().is_err());
+        assert!(acks.expect("main").is_none());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::{Anchor, MonitorSelection, Position};
+
+/// A point in screen coordinates, with `y` growing downwards.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Size {
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A rectangle in screen coordinates, from its top-left corner.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= self.x
+            && point.x < self.x + self.width
+            && point.y >= self.y
+            && point.y < self.y + self.height
+    }
+
+    /// Moves a window of `size` at `origin` so that it lies within the rectangle, keeping
+    /// its top-left corner visible if it is too large to fit.
+    pub fn clamp(&self, origin: Point, size: Size) -> Point {
+        Point {
+            x: clamp_axis(origin.x, size.width, self.x, self.width),
+            y: clamp_axis(origin.y, size.height, self.y, self.height),
+        }
+    }
+
+    fn center(&self) -> Point {
+        Point {
+            x: self.x + self.width / 2.0,
+            y: self.y + self.height / 2.0,
+        }
+    }
+}
+
+fn clamp_axis(value: f64, length: f64, start: f64, extent: f64) -> f64 {
+    value.min(start + extent - length).max(start)
+}
+
+/// A monitor as seen by [`select_screen`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Screen<'a> {
+    pub name: Option<&'a str>,
+    pub frame: Rect,
+    pub primary: bool,
+}
+
+/// Returns the index of the screen a window should be shown on, falling back to the screen
+/// with the cursor, then to the primary screen, then to the first one.
+///
+/// `focused_window` is the frame of the focused window of the app, if any.
+pub fn select_screen(
+    selection: &MonitorSelection,
+    screens: &[Screen],
+    cursor: Option<Point>,
+    focused_window: Option<Rect>,
+) -> Option<usize> {
+    let containing = |point: Option<Point>| point.and_then(|point| screens.iter().position(|screen| screen.frame.contains(point)));
+    let selected = match selection {
+        MonitorSelection::Cursor => None,
+        MonitorSelection::FocusedWindow => containing(focused_window.map(|frame| frame.center())),
+        MonitorSelection::Primary => screens.iter().position(|screen| screen.primary),
+        MonitorSelection::Named(name) => screens.iter().position(|screen| screen.name == Some(name.as_str())),
+    };
+    selected
+        .or_else(|| containing(cursor))
+        .or_else(|| screens.iter().position(|screen| screen.primary))
+        .or(if screens.is_empty() { None } else { Some(0) })
+}
+
+impl Anchor {
+    /// Horizontal and vertical alignment of the anchor, from 0 (left, top) to 1 (right, bottom).
+    fn alignment(&self) -> (f64, f64) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+/// Returns the top-left corner of a window of `size` shown on `area` at `position`.
+///
+/// `scale_factor` converts the logical offsets of `Position::Fixed` into the units of `area`.
+/// `Position::Cursor` and `Position::LastPosition` fall back to the center when `cursor`
+/// or `last_position` is unknown.
+pub fn place(
+    position: &Position,
+    area: Rect,
+    size: Size,
+    scale_factor: f64,
+    cursor: Option<Point>,
+    last_position: Option<Point>,
+) -> Point {
+    let center = Point {
+        x: area.x + (area.width - size.width) / 2.0,
+        y: area.y + (area.height - size.height) / 2.0,
+    };
+    let origin = match position {
+        Position::Center => center,
+        Position::UpperThird => Point {
+            x: center.x,
+            y: area.y + area.height / 3.0 - size.height / 2.0,
+        },
+        Position::Cursor => cursor.unwrap_or(center),
+        Position::Fixed { anchor, offset_x, offset_y } => {
+            let (horizontal, vertical) = anchor.alignment();
+            Point {
+                x: area.x + (area.width - size.width) * horizontal + offset_x * scale_factor,
+                y: area.y + (area.height - size.height) * vertical + offset_y * scale_factor,
+            }
+        }
+        // clamped like the others, the monitor it was left on may be gone or rearranged
+        Position::LastPosition => last_position.unwrap_or(center),
+    };
+    area.clamp(origin, size)
+}
+
+/// Returns the frame of a window restored on a monitor at `frame`, from `saved`, its frame
+/// relative to the top-left corner of the monitor. The window is shrunk and moved to fit `area`.
+pub fn restore(saved: Rect, frame: Rect, area: Rect) -> Rect {
+    let size = Size {
+        width: saved.width.min(area.width),
+        height: saved.height.min(area.height),
+    };
+    let origin = area.clamp(Point { x: frame.x + saved.x, y: frame.y + saved.y }, size);
+    Rect { x: origin.x, y: origin.y, width: size.width, height: size.height }
+}
+
+/// Returns the frame of a window at `frame` resized to `height`, bounded by `min_height` and
+/// `max_height`. The top edge stays in place unless the window would extend below `area`,
+/// in which case it moves up just enough to fit.
+pub fn fit_height(frame: Rect, height: f64, min_height: Option<f64>, max_height: Option<f64>, area: Option<Rect>) -> Rect {
+    let height = max_height.map_or(height, |max_height| height.min(max_height));
+    let height = min_height.map_or(height, |min_height| height.max(min_height));
+    let resized = Rect { height, ..frame };
+    match area {
+        Some(area) => {
+            let height = height.min(area.height);
+            let y = frame.y.min(area.y + area.height - height).max(area.y);
+            Rect { y, height, ..frame }
+        }
+        None => resized,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fit_height, place, restore, select_screen, Point, Rect, Screen, Size};
+    use crate::{Anchor, MonitorSelection, Position};
+
+    const AREA: Rect = Rect { x: 100.0, y: 0.0, width: 1200.0, height: 900.0 };
+    const SIZE: Size = Size { width: 600.0, height: 300.0 };
+
+    #[test]
+    fn center() {
+        assert_eq!(place(&Position::Center, AREA, SIZE, 1.0, None, None), Point { x: 400.0, y: 300.0 });
+    }
+
+    #[test]
+    fn upper_third() {
+        assert_eq!(place(&Position::UpperThird, AREA, SIZE, 1.0, None, None), Point { x: 400.0, y: 150.0 });
+    }
+
+    #[test]
+    fn cursor_is_clamped_to_area() {
+        let cursor = Point { x: 200.0, y: 100.0 };
+        assert_eq!(place(&Position::Cursor, AREA, SIZE, 1.0, Some(cursor), None), cursor);
+        let cursor = Point { x: 1250.0, y: 850.0 };
+        assert_eq!(place(&Position::Cursor, AREA, SIZE, 1.0, Some(cursor), None), Point { x: 700.0, y: 600.0 });
+        assert_eq!(place(&Position::Cursor, AREA, SIZE, 1.0, None, None), Point { x: 400.0, y: 300.0 });
+    }
+
+    #[test]
+    fn fixed_anchor_with_scaled_offsets() {
+        let position = Position::Fixed { anchor: Anchor::TopRight, offset_x: -10.0, offset_y: 20.0 };
+        assert_eq!(place(&position, AREA, SIZE, 2.0, None, None), Point { x: 680.0, y: 40.0 });
+        let position = Position::Fixed { anchor: Anchor::Bottom, offset_x: 0.0, offset_y: 50.0 };
+        assert_eq!(place(&position, AREA, SIZE, 1.0, None, None), Point { x: 400.0, y: 600.0 });
+    }
+
+    #[test]
+    fn last_position_is_clamped_to_area() {
+        let last_position = Point { x: 150.0, y: 20.0 };
+        assert_eq!(place(&Position::LastPosition, AREA, SIZE, 1.0, None, Some(last_position)), last_position);
+        // left on a monitor that is no longer there
+        let last_position = Point { x: -1500.0, y: 2000.0 };
+        assert_eq!(place(&Position::LastPosition, AREA, SIZE, 1.0, None, Some(last_position)), Point { x: 100.0, y: 600.0 });
+        assert_eq!(place(&Position::LastPosition, AREA, SIZE, 1.0, None, None), Point { x: 400.0, y: 300.0 });
+    }
+
+    #[test]
+    fn window_larger_than_area() {
+        let size = Size { width: 1500.0, height: 1000.0 };
+        assert_eq!(place(&Position::Center, AREA, size, 1.0, None, None), Point { x: 100.0, y: 0.0 });
+    }
+
+    #[test]
+    fn contains() {
+        assert!(AREA.contains(Point { x: 100.0, y: 0.0 }));
+        assert!(!AREA.contains(Point { x: 1300.0, y: 0.0 }));
+    }
+
+    #[test]
+    fn deserialize_position() {
+        let position: Position = serde_json::from_str("\"upper_third\"").unwrap();
+        assert_eq!(position, Position::UpperThird);
+        let position: Position = serde_json::from_value(serde_json::json!({
+            "fixed": { "anchor": "top_right", "offset_y": 40 },
+        })).unwrap();
+        assert_eq!(position, Position::Fixed { anchor: Anchor::TopRight, offset_x: 0.0, offset_y: 40.0 });
+    }
+
+    fn screens() -> Vec<Screen<'static>> {
+        vec![
+            Screen { name: Some("left"), frame: Rect { x: -1920.0, y: 0.0, width: 1920.0, height: 1080.0 }, primary: false },
+            Screen { name: Some("main"), frame: Rect { x: 0.0, y: 0.0, width: 2560.0, height: 1440.0 }, primary: true },
+            Screen { name: Some("right"), frame: Rect { x: 2560.0, y: 0.0, width: 1920.0, height: 1080.0 }, primary: false },
+        ]
+    }
+
+    #[test]
+    fn select_screen_by_policy() {
+        let screens = screens();
+        let cursor = Some(Point { x: 3000.0, y: 500.0 });
+        let focused_window = Some(Rect { x: -1000.0, y: 100.0, width: 800.0, height: 600.0 });
+        assert_eq!(select_screen(&MonitorSelection::Cursor, &screens, cursor, focused_window), Some(2));
+        assert_eq!(select_screen(&MonitorSelection::FocusedWindow, &screens, cursor, focused_window), Some(0));
+        assert_eq!(select_screen(&MonitorSelection::Primary, &screens, cursor, focused_window), Some(1));
+        let named = MonitorSelection::Named(String::from("left"));
+        assert_eq!(select_screen(&named, &screens, cursor, None), Some(0));
+    }
+
+    #[test]
+    fn select_screen_fallbacks() {
+        let screens = screens();
+        let cursor = Some(Point { x: 3000.0, y: 500.0 });
+        let missing = MonitorSelection::Named(String::from("missing"));
+        assert_eq!(select_screen(&missing, &screens, cursor, None), Some(2));
+        assert_eq!(select_screen(&MonitorSelection::FocusedWindow, &screens, cursor, None), Some(2));
+        assert_eq!(select_screen(&MonitorSelection::Cursor, &screens, None, None), Some(1));
+        assert_eq!(select_screen(&MonitorSelection::Cursor, &[], cursor, None), None);
+    }
+
+    #[test]
+    fn restore_relative_to_monitor() {
+        let frame = Rect { x: 2560.0, y: 0.0, width: 1920.0, height: 1080.0 };
+        let area = Rect { y: 25.0, height: 1055.0, ..frame };
+        let saved = Rect { x: 100.0, y: 200.0, width: 800.0, height: 400.0 };
+        assert_eq!(restore(saved, frame, area), Rect { x: 2660.0, y: 200.0, ..saved });
+        let saved = Rect { x: 1500.0, y: 0.0, width: 800.0, height: 1200.0 };
+        assert_eq!(restore(saved, frame, area), Rect { x: 3680.0, y: 25.0, width: 800.0, height: 1055.0 });
+    }
+
+    #[test]
+    fn fit_height_keeps_top_edge() {
+        let frame = Rect { x: 400.0, y: 150.0, width: 600.0, height: 60.0 };
+        assert_eq!(fit_height(frame, 300.0, None, None, Some(AREA)), Rect { height: 300.0, ..frame });
+        assert_eq!(fit_height(frame, 20.0, Some(60.0), Some(500.0), Some(AREA)), frame);
+        assert_eq!(fit_height(frame, 800.0, Some(60.0), Some(500.0), None), Rect { height: 500.0, ..frame });
+    }
+
+    #[test]
+    fn fit_height_within_area() {
+        let frame = Rect { x: 400.0, y: 700.0, width: 600.0, height: 60.0 };
+        assert_eq!(fit_height(frame, 300.0, None, None, Some(AREA)), Rect { y: 600.0, height: 300.0, ..frame });
+        assert_eq!(fit_height(frame, 1200.0, None, None, Some(AREA)), Rect { y: 0.0, height: 900.0, ..frame });
+    }
+
+    #[test]
+    fn deserialize_monitor_selection() {
+        let selection: MonitorSelection = serde_json::from_str("\"focused_window\"").unwrap();
+        assert_eq!(selection, MonitorSelection::FocusedWindow);
+        let selection: MonitorSelection = serde_json::from_value(serde_json::json!({ "named": "DELL U2720Q" })).unwrap();
+        assert_eq!(selection, MonitorSelection::Named(String::from("DELL U2720Q")));
+    }
+}
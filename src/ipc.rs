@@ -0,0 +1,296 @@
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{fs::{DirBuilderExt, FileTypeExt, PermissionsExt}, net::{UnixListener, UnixStream}},
+    path::{Path, PathBuf},
+    process,
+    sync::mpsc::channel,
+    thread,
+    time::Duration,
+};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use crate::{Error, Reason, SpotlightManager, Visibility, QUERY_EVENT};
+use crate::error::report_error;
+use crate::visibility::Request;
+
+/// A request to the IPC socket, written as a single line of JSON, e.g.
+/// `{"command":"toggle","label":"main","query":"foo"}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum IpcRequest {
+    /// Shows the window, after sending it `query` in a `spotlight://query` event
+    Show {
+        label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        query: Option<String>,
+    },
+    Hide {
+        label: String,
+    },
+    /// Toggles the window, `query` is only sent if the window is about to be shown
+    Toggle {
+        label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        query: Option<String>,
+    },
+    /// Reports the visibility of the window, or of every spotlight window without a label
+    Status {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+    },
+    SetShortcut {
+        label: String,
+        shortcut: String,
+    },
+}
+
+/// The answer to a request, written as a single line of JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IpcResponse {
+    pub ok: bool,
+    /// The windows reported by a `status` request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub windows: Option<Vec<WindowStatus>>,
+    /// The serialized `Error` of a failed request, with its `code` and `message`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WindowStatus {
+    pub label: String,
+    pub visibility: Visibility,
+}
+
+impl IpcResponse {
+    fn new(result: Result<Option<Vec<WindowStatus>>, Error>) -> Self {
+        match result {
+            Ok(windows) => Self { ok: true, windows, error: None },
+            Err(err) => Self {
+                ok: false,
+                windows: None,
+                error: serde_json::to_value(&err).ok(),
+            },
+        }
+    }
+}
+
+/// Pause after a failed accept, doubled on each consecutive failure up to `MAX_ACCEPT_BACKOFF`.
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Location of the socket of the app with the bundle identifier `identifier`, in
+/// `$XDG_RUNTIME_DIR` or in the temporary directory when it isn't set.
+pub fn socket_path(identifier: &str) -> PathBuf {
+    let dir = env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+    dir.join(format!("{}.spotlight.sock", identifier))
+}
+
+/// Listens on the socket of the app and answers every client on a thread of its own.
+pub(crate) fn start<R: Runtime>(app_handle: AppHandle<R>) -> Result<(), Error> {
+    let listener = bind(&socket_path(&app_handle.config().tauri.bundle.identifier))?;
+    thread::spawn(move || {
+        let mut backoff = MIN_ACCEPT_BACKOFF;
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    backoff = MIN_ACCEPT_BACKOFF;
+                    let app_handle = app_handle.clone();
+                    // a client going away only ends its own connection
+                    thread::spawn(move || serve(&app_handle, stream));
+                }
+                Err(err) => {
+                    report_error(&app_handle, err.into());
+                    // errors like running out of file descriptors last until a client goes away
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Binds the socket at `path`, taking over the socket left behind by an instance that is no
+/// longer running.
+///
+/// The socket is bound in a directory only the user can enter and moved to `path` once only the
+/// user may connect to it, so other users can't connect in between.
+fn bind(path: &Path) -> Result<UnixListener, Error> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.file_type().is_socket() => {
+            return Err(Error::Other(format!("{} exists and is not a socket", path.display())));
+        }
+        Ok(_) => {
+            if UnixStream::connect(path).is_ok() {
+                return Err(Error::Other(format!("{} is used by another instance", path.display())));
+            }
+            // left behind by an instance that did not exit cleanly
+            fs::remove_file(path)?;
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)?;
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let staging_dir = dir.join(format!(".{}.{}", file_name, process::id()));
+    fs::DirBuilder::new().mode(0o700).create(&staging_dir)?;
+    let result = bind_staged(&staging_dir.join("socket"), path);
+    let _ = fs::remove_dir_all(&staging_dir);
+    result
+}
+
+fn bind_staged(staged: &Path, path: &Path) -> Result<UnixListener, Error> {
+    let listener = UnixListener::bind(staged)?;
+    fs::set_permissions(staged, fs::Permissions::from_mode(0o600))?;
+    fs::rename(staged, path)?;
+    Ok(listener)
+}
+
+fn serve<R: Runtime>(app_handle: &AppHandle<R>, stream: UnixStream) -> Result<(), Error> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = serde_json::from_str(&line)
+            .map_err(Error::from)
+            .and_then(|request| handle_on_main_thread(app_handle, request));
+        serde_json::to_writer(&mut writer, &IpcResponse::new(result))?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Handles `request` on the main thread, like shortcuts and commands.
+fn handle_on_main_thread<R: Runtime>(app_handle: &AppHandle<R>, request: IpcRequest) -> Result<Option<Vec<WindowStatus>>, Error> {
+    let (tx, rx) = channel();
+    let app_handle_ = app_handle.clone();
+    app_handle.run_on_main_thread(move || {
+        let _ = tx.send(handle(&app_handle_, request));
+    })?;
+    rx.recv()
+        .map_err(|_| Error::Other(String::from("the app exited before handling the request")))?
+}
+
+fn handle<R: Runtime>(app_handle: &AppHandle<R>, request: IpcRequest) -> Result<Option<Vec<WindowStatus>>, Error> {
+    let manager = app_handle.state::<SpotlightManager>();
+    let (label, request, query) = match request {
+        IpcRequest::Show { label, query } => (label, Request::Show, query),
+        IpcRequest::Hide { label } => (label, Request::Hide, None),
+        IpcRequest::Toggle { label, query } => (label, Request::Toggle, query),
+        IpcRequest::Status { label } => {
+            let labels = match label {
+                Some(label) => vec![label],
+                None => manager.registered_windows()?,
+            };
+            let mut windows = Vec::new();
+            for label in labels {
+                let visibility = manager.visibility(&label)?;
+                windows.push(WindowStatus { label, visibility });
+            }
+            return Ok(Some(windows));
+        }
+        IpcRequest::SetShortcut { label, shortcut } => {
+            manager.set_shortcut(app_handle, &label, shortcut.parse()?)?;
+            return Ok(None);
+        }
+    };
+    let window = app_handle
+        .get_window(&label)
+        .ok_or_else(|| Error::WindowNotFound { label: label.clone() })?;
+    if let Some(query) = query {
+        // sent before the window is shown, so that it opens on the results
+        if request == Request::Show || !manager.visibility(&label)?.is_visible() {
+            window.emit(QUERY_EVENT, query)?;
+        }
+    }
+    manager.request(&window, request, Reason::External)?;
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::{BufRead, BufReader, Write}, os::unix::{fs::PermissionsExt, net::UnixStream}, process, thread};
+    use tauri::test::{mock_builder, mock_context, noop_assets};
+    use crate::{PluginConfig, Visibility, WindowConfig};
+    use super::{bind, serve, IpcRequest, IpcResponse, WindowStatus};
+
+    #[test]
+    fn parse_requests() {
+        let request: IpcRequest = serde_json::from_str(r#"{"command":"toggle","label":"main","query":"foo"}"#).unwrap();
+        assert_eq!(request, IpcRequest::Toggle { label: String::from("main"), query: Some(String::from("foo")) });
+        let request: IpcRequest = serde_json::from_str(r#"{"command":"status"}"#).unwrap();
+        assert_eq!(request, IpcRequest::Status { label: None });
+        let request = IpcRequest::SetShortcut { label: String::from("main"), shortcut: String::from("Ctrl+K") };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"command":"set_shortcut","label":"main","shortcut":"Ctrl+K"}"#
+        );
+    }
+
+    #[test]
+    fn keep_files_that_are_not_sockets() {
+        let path = env::temp_dir().join(format!("spotlight-ipc-file-{}", process::id()));
+        fs::write(&path, "data").unwrap();
+        assert!(bind(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "data");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn answer_requests_over_socket() {
+        let app = mock_builder()
+            .plugin(crate::init(Some(PluginConfig {
+                windows: Some(vec![WindowConfig::for_test("main", "Ctrl+Shift+J")]),
+                ..Default::default()
+            })))
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        let path = env::temp_dir().join("spotlight-ipc-test.sock");
+        let listener = bind(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        let staging_dir = env::temp_dir().join(format!(".spotlight-ipc-test.sock.{}", process::id()));
+        assert!(!staging_dir.exists());
+        // a second instance can not take over the socket
+        assert!(bind(&path).is_err());
+        let app_handle = app.handle();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let _ = serve(&app_handle, stream.unwrap());
+            }
+        });
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut send = |request: &str| {
+            writeln!(stream, "{}", request).unwrap();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            serde_json::from_str::<IpcResponse>(&line).unwrap()
+        };
+
+        assert!(send(r#"{"command":"hide","label":"main"}"#).ok);
+        let response = send(r#"{"command":"status"}"#);
+        assert_eq!(
+            response.windows,
+            Some(vec![WindowStatus { label: String::from("main"), visibility: Visibility::Hidden }])
+        );
+        assert!(send(r#"{"command":"toggle","label":"main","query":"foo"}"#).ok);
+        let response = send(r#"{"command":"status","label":"main"}"#);
+        assert_eq!(response.windows.unwrap()[0].visibility, Visibility::Visible);
+
+        let response = send(r#"{"command":"show","label":"settings"}"#);
+        assert!(!response.ok);
+        assert_eq!(response.error.unwrap()["code"], "window_not_found");
+        let response = send("toggle main");
+        assert_eq!(response.error.unwrap()["code"], "json");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,52 +1,305 @@
 #[cfg_attr(target_os = "macos", path = "spotlight_macos/mod.rs")]
-#[cfg_attr(not(target_os = "macos"), path = "spotlight_others.rs")]
+#[cfg_attr(target_os = "linux", path = "spotlight_linux/mod.rs")]
+#[cfg_attr(not(any(target_os = "macos", target_os = "linux")), path = "spotlight_others.rs")]
 mod spotlight;
+#[cfg(all(target_os = "linux", feature = "dbus"))]
+mod dbus;
 mod error;
+mod event;
 mod config;
+mod conflict;
+mod geometry;
+#[cfg(unix)]
+mod ipc;
+mod manager;
+mod overrides;
+#[cfg(all(target_os = "linux", feature = "portal"))]
+mod portal;
+mod saved_geometry;
+mod scope;
+mod shortcut;
+mod shortcut_backend;
+#[cfg(all(test, target_os = "linux", any(feature = "dbus", feature = "portal")))]
+mod test_bus;
+mod visibility;
 
-pub use config::{PluginConfig, WindowConfig};
-pub use error::Error;
+pub use config::{Anchor, CloseShortcutMode, MonitorSelection, PluginConfig, Position, WindowConfig};
+pub use conflict::{ShortcutConflict, ShortcutOwner};
+pub use error::{Error, ErrorContext, ERROR_EVENT};
+pub use event::{
+    Reason, SpotlightEvent, SpotlightEventKind, HIDDEN_EVENT, QUERY_EVENT, SHOWN_EVENT, WILL_HIDE_EVENT, WILL_SHOW_EVENT,
+};
+#[cfg(unix)]
+pub use ipc::{socket_path, IpcRequest, IpcResponse, WindowStatus};
+pub use overrides::ShortcutOverrides;
+pub use scope::CommandScope;
+pub use shortcut::{KeyBinding, Modifier, Shortcut, ShortcutParseError};
+pub use shortcut_backend::{FakeShortcutBackend, ShortcutBackend, ShortcutHandler, ShortcutState, TauriShortcutBackend};
+pub use manager::SpotlightManager;
+pub use visibility::Visibility;
 
+use std::sync::Arc;
+use error::report_error;
+use visibility::Request;
+use overrides::shortcuts_path;
 use tauri::{
     plugin::{Builder, TauriPlugin},
-    Manager, Wry, Runtime, State, Window
+    AppHandle, Manager, Runtime, State, Window
 };
 
 pub trait ManagerExt<R: Runtime> {
-    fn spotlight(&self) -> State<'_, spotlight::SpotlightManager>;
+    fn spotlight(&self) -> State<'_, SpotlightManager>;
 }
 
 impl<R: Runtime, T: Manager<R>> ManagerExt<R> for T {
-  fn spotlight(&self) -> State<'_, spotlight::SpotlightManager> {
-    self.state::<spotlight::SpotlightManager>()
+  fn spotlight(&self) -> State<'_, SpotlightManager> {
+    self.state::<SpotlightManager>()
   }
 }
 
+/// Checks that the calling window may invoke `command` on window `label`, or on itself
+/// if no label is given, and returns the label of the target.
+fn authorize<R: Runtime>(manager: &SpotlightManager, command: &str, window: &Window<R>, label: Option<String>) -> Result<String, Error> {
+    let label = label.unwrap_or_else(|| window.label().to_string());
+    manager.config.check_command_scope(command, window.label(), &label)?;
+    Ok(label)
+}
+
+/// Resolves the window a command acts on: the one named by `label`, or the calling window.
+fn target_window<R: Runtime>(manager: &SpotlightManager, command: &str, window: Window<R>, label: Option<String>) -> Result<Window<R>, Error> {
+    let label = authorize(manager, command, &window, label)?;
+    if label == window.label() {
+        return Ok(window);
+    }
+    window.get_window(&label).ok_or(Error::WindowNotFound { label })
+}
+
+#[tauri::command]
+fn show<R: Runtime>(manager: State<'_, SpotlightManager>, window: Window<R>, label: Option<String>) -> Result<(), Error> {
+    let window = target_window(&manager, "show", window, label)?;
+    manager.request(&window, Request::Show, Reason::Command)
+}
+
+#[tauri::command]
+fn hide<R: Runtime>(manager: State<'_, SpotlightManager>, window: Window<R>, label: Option<String>) -> Result<(), Error> {
+    let window = target_window(&manager, "hide", window, label)?;
+    manager.request(&window, Request::Hide, Reason::Command)
+}
+
+#[tauri::command]
+fn toggle<R: Runtime>(manager: State<'_, SpotlightManager>, window: Window<R>, label: Option<String>) -> Result<(), Error> {
+    let window = target_window(&manager, "toggle", window, label)?;
+    manager.request(&window, Request::Toggle, Reason::Command)
+}
+
+#[tauri::command]
+fn is_visible<R: Runtime>(manager: State<'_, SpotlightManager>, window: Window<R>, label: Option<String>) -> Result<bool, Error> {
+    let label = authorize(&manager, "is_visible", &window, label)?;
+    Ok(manager.visibility(&label)?.is_visible())
+}
+
+#[tauri::command]
+fn set_content_height<R: Runtime>(manager: State<'_, SpotlightManager>, window: Window<R>, label: Option<String>, height: f64) -> Result<(), Error> {
+    let window = target_window(&manager, "set_content_height", window, label)?;
+    manager.set_content_height(&window, height)
+}
+
+/// Hides the calling window if it is a spotlight window, used by the injected
+/// keydown listener when `close_shortcut_mode` is `local`.
+#[tauri::command]
+fn close<R: Runtime>(manager: State<'_, SpotlightManager>, window: Window<R>) -> Result<(), Error> {
+    match manager.request(&window, Request::Hide, Reason::CloseShortcut) {
+        Err(Error::WindowNotRegistered { .. }) => Ok(()),
+        result => result,
+    }
+}
+
+/// Called by the injected script once the webview handled `spotlight://will-show`.
+#[tauri::command]
+fn will_show_handled<R: Runtime>(manager: State<'_, SpotlightManager>, window: Window<R>, sequence: Option<u64>) {
+    manager.will_show_handled(window.label(), sequence);
+}
+
+#[tauri::command]
+fn close_key_binding(manager: State<'_, SpotlightManager>) -> Option<KeyBinding> {
+    manager.config.local_close_shortcut().map(KeyBinding::from)
+}
+
+#[tauri::command]
+fn register<R: Runtime>(app_handle: AppHandle<R>, manager: State<'_, SpotlightManager>, window: Window<R>, config: WindowConfig) -> Result<(), Error> {
+    authorize(&manager, "register", &window, Some(config.label.clone()))?;
+    let window = app_handle
+        .get_window(&config.label)
+        .ok_or_else(|| Error::WindowNotFound { label: config.label.clone() })?;
+    manager.register_window(&window, config)
+}
+
 #[tauri::command]
-fn show(manager: State<'_, spotlight::SpotlightManager>, window: Window<Wry>) -> Result<(), String> {
-    manager.show(&window).map_err(|err| format!("{:?}", err))
+fn unregister<R: Runtime>(app_handle: AppHandle<R>, manager: State<'_, SpotlightManager>, window: Window<R>, label: String) -> Result<(), Error> {
+    let label = authorize(&manager, "unregister", &window, Some(label))?;
+    manager.unregister_window(&app_handle, &label)
 }
 
 #[tauri::command]
-fn hide(manager: State<'_, spotlight::SpotlightManager>, window: Window<Wry>) -> Result<(), String> {
-    manager.hide(&window).map_err(|err| format!("{:?}", err))
+fn set_shortcut<R: Runtime>(app_handle: AppHandle<R>, manager: State<'_, SpotlightManager>, window: Window<R>, label: String, shortcut: String) -> Result<(), Error> {
+    let label = authorize(&manager, "set_shortcut", &window, Some(label))?;
+    manager.set_shortcut(&app_handle, &label, shortcut.parse()?)
+}
+
+/// Picks the GlobalShortcuts portal when it is available in a Wayland session, and the global
+/// shortcut manager of Tauri otherwise.
+#[cfg(all(target_os = "linux", feature = "portal"))]
+fn default_shortcut_backend<R: Runtime>(app_handle: &AppHandle<R>, config: &mut PluginConfig) -> Arc<dyn ShortcutBackend> {
+    match portal::PortalShortcutBackend::connect(app_handle.clone()) {
+        Ok(backend) => {
+            // rebinding the close shortcut on every focus change may prompt the user each time
+            config.close_shortcut_mode.get_or_insert(CloseShortcutMode::Local);
+            Arc::new(backend)
+        }
+        Err(_) => Arc::new(TauriShortcutBackend::new(app_handle.clone())),
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "portal")))]
+fn default_shortcut_backend<R: Runtime>(app_handle: &AppHandle<R>, _config: &mut PluginConfig) -> Arc<dyn ShortcutBackend> {
+    Arc::new(TauriShortcutBackend::new(app_handle.clone()))
 }
 
-pub fn init(spotlight_config: Option<PluginConfig>) -> TauriPlugin<Wry, Option<PluginConfig>> {
-    Builder::<Wry, Option<PluginConfig>>::new("spotlight")
-        .invoke_handler(tauri::generate_handler![show, hide])
+pub fn init<R: Runtime>(spotlight_config: Option<PluginConfig>) -> TauriPlugin<R, Option<PluginConfig>> {
+    build_plugin(spotlight_config, None)
+}
+
+/// Like `init`, with the shortcuts registered with `shortcut_backend`, e.g. a
+/// `FakeShortcutBackend` to press shortcuts in tests.
+pub fn init_with_shortcut_backend<R: Runtime, B: ShortcutBackend + 'static>(
+    spotlight_config: Option<PluginConfig>,
+    shortcut_backend: B,
+) -> TauriPlugin<R, Option<PluginConfig>> {
+    build_plugin(spotlight_config, Some(Arc::new(shortcut_backend)))
+}
+
+fn build_plugin<R: Runtime>(
+    spotlight_config: Option<PluginConfig>,
+    shortcut_backend: Option<Arc<dyn ShortcutBackend>>,
+) -> TauriPlugin<R, Option<PluginConfig>> {
+    Builder::<R, Option<PluginConfig>>::new("spotlight")
+        .invoke_handler(tauri::generate_handler![
+            show,
+            hide,
+            toggle,
+            is_visible,
+            set_content_height,
+            close,
+            close_key_binding,
+            will_show_handled,
+            register,
+            unregister,
+            set_shortcut,
+        ])
+        .js_init_script(String::from(concat!(include_str!("close_key_binding.js"), include_str!("will_show.js"))))
+        .on_page_load(|window, _| {
+            if let Some(manager) = window.try_state::<SpotlightManager>() {
+                manager.page_loaded(window.label());
+            }
+        })
         .setup_with_config(|app, config| {
-            app.manage(spotlight::SpotlightManager::new(
-                PluginConfig::merge(
-                    &spotlight_config.unwrap_or(PluginConfig::default()),
-                    &config.unwrap_or(PluginConfig::default()),
-                )
-            ));
+            let mut config = PluginConfig::merge(
+                &spotlight_config.unwrap_or_default(),
+                &config.unwrap_or_default(),
+            );
+            let mut load_error = None;
+            if config.persist_shortcuts.unwrap_or(false) {
+                match shortcuts_path(app).and_then(|path| ShortcutOverrides::load(&path)) {
+                    Ok(overrides) => overrides.apply(&mut config),
+                    Err(err) => load_error = Some(err),
+                }
+            }
+            let shortcut_backend = match shortcut_backend {
+                Some(shortcut_backend) => shortcut_backend,
+                None => default_shortcut_backend(app, &mut config),
+            };
+            let conflicts = config.find_conflicts(|shortcut| {
+                shortcut_backend.is_registered(shortcut).unwrap_or(false)
+            });
+            if !conflicts.is_empty() {
+                return Err(Box::new(Error::ShortcutConflict(conflicts)));
+            }
+            #[cfg(unix)]
+            let ipc_socket = config.ipc_socket.unwrap_or(false);
+            let manager = SpotlightManager::new(config);
+            manager.set_shortcut_backend(shortcut_backend);
+            app.manage(manager);
+            #[cfg(unix)]
+            if ipc_socket {
+                if let Err(err) = ipc::start(app.clone()) {
+                    report_error(app, err);
+                }
+            }
+            #[cfg(all(target_os = "linux", feature = "dbus"))]
+            dbus::start(app.clone());
+            if let Some(err) = load_error {
+                report_error(app, err);
+            }
             Ok(())
         })
         .on_webview_ready(move |window| {
             let app_handle = window.app_handle();
-            app_handle.spotlight().init_spotlight_window(&window).unwrap();
+            if let Err(err) = app_handle.spotlight().init_spotlight_window(&window) {
+                report_error(&app_handle, err);
+            }
         })
         .build()
 }
+
+#[cfg(test)]
+mod tests {
+    use tauri::{
+        test::{mock_builder, mock_context, noop_assets},
+        Manager,
+    };
+    use crate::{CommandScope, Error, PluginConfig, SpotlightManager, WindowConfig};
+
+    #[test]
+    fn commands_target_window_by_label() {
+        let app = mock_builder()
+            .plugin(crate::init(Some(PluginConfig {
+                windows: Some(vec![WindowConfig::for_test("main", "Ctrl+Shift+J")]),
+                ..Default::default()
+            })))
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        let window = app.get_window("main").unwrap();
+        let label = Some(String::from("main"));
+
+        super::toggle(app.state::<SpotlightManager>(), window.clone(), label.clone()).unwrap();
+        assert!(!super::is_visible(app.state::<SpotlightManager>(), window.clone(), label.clone()).unwrap());
+        super::show(app.state::<SpotlightManager>(), window.clone(), label.clone()).unwrap();
+        assert!(super::is_visible(app.state::<SpotlightManager>(), window.clone(), None).unwrap());
+        super::set_content_height(app.state::<SpotlightManager>(), window.clone(), label.clone(), 240.0).unwrap();
+
+        let err = super::hide(app.state::<SpotlightManager>(), window, Some(String::from("settings"))).unwrap_err();
+        assert!(matches!(err, Error::WindowNotFound { label } if label == "settings"));
+    }
+
+    #[test]
+    fn commands_respect_scopes() {
+        let app = mock_builder()
+            .plugin(crate::init(Some(PluginConfig {
+                windows: Some(vec![WindowConfig::for_test("main", "Ctrl+Shift+J")]),
+                command_scopes: Some(vec![CommandScope {
+                    windows: vec![String::from("main")],
+                    commands: vec![String::from("hide")],
+                    targets: None,
+                }]),
+                ..Default::default()
+            })))
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        let window = app.get_window("main").unwrap();
+
+        super::hide(app.state::<SpotlightManager>(), window.clone(), None).unwrap();
+        let err = super::show(app.state::<SpotlightManager>(), window, None).unwrap_err();
+        assert!(matches!(err, Error::CommandNotAllowed { command, caller, target }
+            if command == "show" && caller == "main" && target == "main"));
+    }
+}
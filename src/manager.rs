@@ -0,0 +1,1248 @@
+use std::{
+    collections::HashMap,
+    sync::{atomic::{AtomicBool, Ordering}, mpsc::Receiver, Arc, Mutex},
+};
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, Runtime, Window, WindowEvent,
+};
+use crate::{MonitorSelection, PluginConfig, Position, Shortcut, WindowConfig};
+use crate::Error;
+use crate::error::{report_error, ErrorHandler};
+use crate::conflict::check_window_conflicts;
+use crate::overrides::{load_shortcut, save_shortcut};
+use crate::saved_geometry::{monitor_key, GeometryStore};
+use crate::geometry::{self, Point, Rect, Screen, Size};
+use crate::event::{
+    will_show_ack_script, EventListeners, Hooks, Reason, SpotlightEvent, SpotlightEventKind, WillShowAcks, WILL_SHOW_TIMEOUT,
+};
+use crate::visibility::{Request, Visibility};
+use crate::shortcut_backend::{ShortcutBackend, ShortcutBackendSlot, ShortcutState};
+use crate::spotlight::CurrentPlatform;
+
+/// Platform specifics of spotlight windows. The defaults work with the windows of Tauri, in
+/// physical pixels, and each backend overrides what its platform does differently:
+/// `spotlight_linux` with GTK, `spotlight_macos` with an `NSPanel` in points.
+pub(crate) trait Platform {
+    /// Turns a window that was just registered into a spotlight window.
+    fn init_window<R: Runtime>(_window: &Window<R>, _window_config: &WindowConfig) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called before the window is shown.
+    fn will_show<R: Runtime>(_window: &Window<R>) {}
+
+    /// Shows the window and gives it the keyboard focus.
+    fn show_window<R: Runtime>(window: &Window<R>) -> Result<(), Error> {
+        let label = window.label().to_string();
+        window.show().map_err(|_| Error::FailedToShowWindow { label: label.clone() })?;
+        window.set_focus().map_err(|_| Error::FailedToShowWindow { label })
+    }
+
+    fn hide_window<R: Runtime>(window: &Window<R>) -> Result<(), Error> {
+        window.hide().map_err(|_| Error::FailedToHideWindow { label: window.label().to_string() })
+    }
+
+    /// Called once the window is hidden.
+    fn did_hide<R: Runtime>(_window: &Window<R>, _reason: Reason) {}
+
+    /// Returns the position of the window and the size `set_size` takes.
+    fn frame<R: Runtime>(window: &Window<R>) -> Result<Rect, Error> {
+        let position = window.outer_position()?;
+        let size = window.inner_size()?;
+        Ok(Rect {
+            x: position.x as f64,
+            y: position.y as f64,
+            width: size.width as f64,
+            height: size.height as f64,
+        })
+    }
+
+    /// Returns the key and the frame of the monitor containing `point`, see `monitor_key`.
+    fn monitor_at<R: Runtime>(window: &Window<R>, point: Point) -> Result<Option<(String, Rect)>, Error> {
+        let monitor = window.available_monitors()?.into_iter().find(|monitor| monitor_rect(monitor).contains(point));
+        Ok(monitor.map(|monitor| {
+            let frame = monitor_rect(&monitor);
+            (monitor_key(monitor.name().map(String::as_str), frame), frame)
+        }))
+    }
+
+    /// Returns the part of the monitor not covered by panels, docks or the taskbar, in
+    /// physical pixels.
+    fn work_area<R: Runtime>(_window: &Window<R>, monitor: &Monitor) -> Rect {
+        monitor_rect(monitor)
+    }
+
+    /// Returns the cursor position in physical pixels.
+    fn cursor_position<R: Runtime>(_window: &Window<R>, _monitors: &[Monitor]) -> Option<Point> {
+        None
+    }
+
+    /// Places the window at `origin` within `area` if the platform positions it by other means
+    /// than its position, returns `false` otherwise. `position` is `None` when the window is
+    /// restored to a remembered geometry.
+    fn place_window<R: Runtime>(
+        _window: &Window<R>,
+        _monitor: &Monitor,
+        _position: Option<&Position>,
+        _area: Rect,
+        _origin: Point,
+    ) -> bool {
+        false
+    }
+
+    /// Moves the window to `position` on the monitor picked by `monitor_selection`, keeping it
+    /// within the work area of the monitor. Works in physical pixels.
+    ///
+    /// `saved_frames` holds the remembered geometry of the window, which takes precedence over
+    /// `position` on the monitors it was recorded on.
+    fn position_window<R: Runtime>(
+        window: &Window<R>,
+        position: &Position,
+        monitor_selection: &MonitorSelection,
+        last_position: Option<Point>,
+        saved_frames: Option<&HashMap<String, Rect>>,
+    ) -> Result<(), Error> {
+        let monitors = window.available_monitors()?;
+        if monitors.is_empty() {
+            return Ok(());
+        }
+        let cursor = Self::cursor_position(window, &monitors);
+        let primary = window.primary_monitor()?.map(|monitor| monitor_rect(&monitor));
+        let names: Vec<Option<String>> = monitors.iter().map(|monitor| monitor.name().cloned()).collect();
+        let screens: Vec<Screen> = monitors
+            .iter()
+            .zip(&names)
+            .map(|(monitor, name)| Screen {
+                name: name.as_deref(),
+                frame: monitor_rect(monitor),
+                primary: primary == Some(monitor_rect(monitor)),
+            })
+            .collect();
+        let focused_window = focused_window_rect(window)?;
+        let index = match geometry::select_screen(monitor_selection, &screens, cursor, focused_window) {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        let monitor = &monitors[index];
+        let area = Self::work_area(window, monitor);
+        let saved_frame = saved_frames.and_then(|frames| frames.get(&monitor_key(screens[index].name, screens[index].frame)));
+        let origin = match saved_frame {
+            Some(saved_frame) => {
+                let frame = geometry::restore(*saved_frame, screens[index].frame, area);
+                window.set_size(PhysicalSize::new(frame.width.round() as u32, frame.height.round() as u32))?;
+                Point { x: frame.x, y: frame.y }
+            }
+            None => {
+                let size = window.outer_size()?;
+                let size = Size { width: size.width as f64, height: size.height as f64 };
+                geometry::place(position, area, size, monitor.scale_factor(), cursor, last_position)
+            }
+        };
+        if Self::place_window(window, monitor, saved_frame.is_none().then(|| position), area, origin) {
+            return Ok(());
+        }
+        window.set_position(PhysicalPosition::new(origin.x.round() as i32, origin.y.round() as i32))?;
+        Ok(())
+    }
+
+    /// Resizes the window to `height` logical pixels, keeping its top edge in place and the
+    /// window within the work area of its monitor.
+    fn set_content_height<R: Runtime>(
+        window: &Window<R>,
+        height: f64,
+        min_height: Option<f64>,
+        max_height: Option<f64>,
+    ) -> Result<(), Error> {
+        let scale_factor = window.scale_factor()?;
+        let position = window.outer_position()?;
+        let size = window.inner_size()?;
+        let frame = Rect {
+            x: position.x as f64,
+            y: position.y as f64,
+            width: size.width as f64,
+            height: size.height as f64,
+        };
+        let area = window.current_monitor()?.map(|monitor| Self::work_area(window, &monitor));
+        let frame = geometry::fit_height(
+            frame,
+            height * scale_factor,
+            min_height.map(|min_height| min_height * scale_factor),
+            max_height.map(|max_height| max_height * scale_factor),
+            area,
+        );
+        window.set_size(PhysicalSize::new(frame.width.round() as u32, frame.height.round() as u32))?;
+        window.set_position(PhysicalPosition::new(frame.x.round() as i32, frame.y.round() as i32))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct RegisteredWindow {
+    config: WindowConfig,
+    /// Cleared on unregister, since Tauri does not allow removing window event listeners
+    active: Arc<AtomicBool>,
+    visibility: Visibility,
+    /// Position of the window when it was last hidden, see `Platform::frame`
+    last_position: Option<Point>,
+}
+
+#[derive(Default, Debug)]
+pub struct SpotlightManager {
+    pub config: PluginConfig,
+    registered_window: Mutex<HashMap<String, RegisteredWindow>>,
+    /// Configs of destroyed windows, re-applied when a window with the same label is created
+    destroyed_window: Mutex<HashMap<String, WindowConfig>>,
+    pub(crate) error_handler: ErrorHandler,
+    event_listeners: EventListeners,
+    before_show: Hooks,
+    before_hide: Hooks,
+    saved_geometry: GeometryStore,
+    will_show_acks: WillShowAcks,
+    shortcut_backend: ShortcutBackendSlot,
+}
+
+impl SpotlightManager {
+    pub fn new(config: PluginConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Installs a handler for errors raised in shortcut and window event callbacks.
+    pub fn set_error_handler<F: Fn(&Error) + Send + Sync + 'static>(&self, handler: F) {
+        self.error_handler.set(handler);
+    }
+
+    /// Subscribes to the lifecycle events of all spotlight windows.
+    pub fn on_event<F: Fn(&SpotlightEvent) + Send + Sync + 'static>(&self, listener: F) {
+        self.event_listeners.add(listener);
+    }
+
+    /// Adds a hook that runs before a window is shown, with the window label and the
+    /// trigger. The window stays hidden if any hook returns `false`.
+    pub fn before_show<F: Fn(&str, Reason) -> bool + Send + Sync + 'static>(&self, hook: F) {
+        self.before_show.add(hook);
+    }
+
+    /// Adds a hook that runs before a window is hidden, with the window label and the
+    /// trigger. The window stays visible if any hook returns `false`.
+    pub fn before_hide<F: Fn(&str, Reason) -> bool + Send + Sync + 'static>(&self, hook: F) {
+        self.before_hide.add(hook);
+    }
+
+    /// Registers shortcuts with `backend` instead of the global shortcut manager of Tauri.
+    pub(crate) fn set_shortcut_backend(&self, backend: Arc<dyn ShortcutBackend>) {
+        self.shortcut_backend.set(backend);
+    }
+
+    pub(crate) fn shortcut_backend<R: Runtime>(&self, app_handle: &AppHandle<R>) -> Arc<dyn ShortcutBackend> {
+        self.shortcut_backend.get(app_handle)
+    }
+
+    fn get_window_config<R: Runtime>(&self, window: &Window<R>) -> Option<WindowConfig> {
+        if let Some(window_configs) = self.config.windows.clone() {
+            for window_config in window_configs {
+                if window.label() == window_config.label {
+                    return Some(window_config.clone());
+                }
+            }
+        }
+        None
+    }
+
+    fn take_destroyed_window_config(&self, label: &str) -> Result<Option<WindowConfig>, Error> {
+        let mut destroyed_window = self
+            .destroyed_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock destroyed window")))?;
+        Ok(destroyed_window.remove(label))
+    }
+
+    /// Registers the window if it is listed in the plugin config, or if it replaces
+    /// a destroyed spotlight window with the same label.
+    pub fn init_spotlight_window<R: Runtime>(&self, window: &Window<R>) -> Result<(), Error> {
+        let destroyed_window_config = self.take_destroyed_window_config(window.label())?;
+        let window_config = match self.get_window_config(window).or(destroyed_window_config) {
+            Some(window_config) => window_config,
+            None => return Ok(()),
+        };
+        match self.register_window(window, window_config) {
+            Err(Error::WindowAlreadyRegistered { .. }) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Turns the window into a spotlight window, using `window_config` for its shortcut.
+    pub fn register_window<R: Runtime>(&self, window: &Window<R>, window_config: WindowConfig) -> Result<(), Error> {
+        let label = window.label().to_string();
+        let mut window_config = WindowConfig { label: label.clone(), ..window_config };
+        // configured windows already got theirs in `setup`, runtime windows get it here
+        match load_shortcut(&window.app_handle(), &self.config, &label) {
+            Ok(Some(shortcut)) => window_config.shortcut = shortcut,
+            Ok(None) => {}
+            Err(err) => report_error(&window.app_handle(), err),
+        }
+        {
+            let registered_window = self
+                .registered_window
+                .lock()
+                .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?;
+            if registered_window.contains_key(&label) {
+                return Err(Error::WindowAlreadyRegistered { label });
+            }
+            check_window_conflicts(&self.config, registered_window.values().map(|registered| &registered.config), &window_config)?;
+        }
+        // not locked meanwhile, both may wait for the main thread, which may be waiting for the lock
+        register_shortcut_for_window(window, &window_config)?;
+        if let Err(err) = register_close_shortcut(window).and_then(|_| CurrentPlatform::init_window(window, &window_config)) {
+            let _ = unregister_shortcut(&window.app_handle(), &window_config.shortcut);
+            return Err(err);
+        }
+        let mut registered_window = self
+            .registered_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?;
+        if registered_window.contains_key(&label) {
+            // registered by another thread in the meantime
+            let _ = unregister_shortcut(&window.app_handle(), &window_config.shortcut);
+            return Err(Error::WindowAlreadyRegistered { label });
+        }
+        let active = Arc::new(AtomicBool::new(true));
+        handle_focus_state_change(window, active.clone());
+        let visibility = Visibility::from_visible(window.is_visible().unwrap_or(false));
+        registered_window.insert(label, RegisteredWindow {
+            config: window_config,
+            active,
+            visibility,
+            last_position: None,
+        });
+        Ok(())
+    }
+
+    /// Releases the shortcut and window event handling of a registered window.
+    pub fn unregister_window<R: Runtime>(&self, app_handle: &AppHandle<R>, label: &str) -> Result<(), Error> {
+        self.remove_window(app_handle, label)?;
+        Ok(())
+    }
+
+    /// Rebinds the toggle shortcut of a registered window, keeping the old one if
+    /// the new shortcut can not be registered. Failing to save the new shortcut is
+    /// only reported to the error handler.
+    pub fn set_shortcut<R: Runtime>(&self, app_handle: &AppHandle<R>, label: &str, shortcut: Shortcut) -> Result<(), Error> {
+        let window = app_handle
+            .get_window(label)
+            .ok_or_else(|| Error::WindowNotFound { label: String::from(label) })?;
+        let (current, window_config) = {
+            let registered_window = self
+                .registered_window
+                .lock()
+                .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?;
+            let current = registered_window
+                .get(label)
+                .map(|registered| registered.config.clone())
+                .ok_or_else(|| Error::WindowNotRegistered { label: String::from(label) })?;
+            if current.shortcut == shortcut {
+                return Ok(());
+            }
+            let window_config = WindowConfig {
+                shortcut: shortcut.clone(),
+                ..current.clone()
+            };
+            check_window_conflicts(&self.config, registered_window.values().map(|registered| &registered.config), &window_config)?;
+            (current, window_config)
+        };
+        // not locked meanwhile, the shortcut backend may wait for the main thread, which may be
+        // waiting for the lock
+        unregister_shortcut(app_handle, &current.shortcut)?;
+        if let Err(err) = register_shortcut_for_window(&window, &window_config) {
+            // the caller needs to know why the new shortcut failed, not why the old one did
+            if let Err(rollback_err) = register_shortcut_for_window(&window, &current) {
+                report_error(app_handle, rollback_err);
+            }
+            return Err(err);
+        }
+        let mut registered_window = self
+            .registered_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?;
+        match registered_window.get_mut(label) {
+            Some(registered) if registered.config.shortcut == current.shortcut => registered.config = window_config,
+            registered => {
+                // unregistered or rebound by another thread in the meantime
+                let err = match registered {
+                    Some(_) => Error::Other(format!("shortcut of window {} changed in the meantime", label)),
+                    None => Error::WindowNotRegistered { label: String::from(label) },
+                };
+                std::mem::drop(registered_window);
+                let _ = unregister_shortcut(app_handle, &shortcut);
+                return Err(err);
+            }
+        }
+        std::mem::drop(registered_window);
+        // the shortcut is already in effect, it just won't survive a restart
+        if let Err(err) = save_shortcut(app_handle, &self.config, label, shortcut) {
+            report_error(app_handle, err);
+        }
+        Ok(())
+    }
+
+    fn handle_window_destroyed<R: Runtime>(&self, app_handle: &AppHandle<R>, label: &str) -> Result<(), Error> {
+        self.will_show_acks.forget(label);
+        let window_config = self.remove_window(app_handle, label)?;
+        self.destroyed_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock destroyed window")))?
+            .insert(String::from(label), window_config);
+        Ok(())
+    }
+
+    fn remove_window<R: Runtime>(&self, app_handle: &AppHandle<R>, label: &str) -> Result<WindowConfig, Error> {
+        let mut registered_window = self
+            .registered_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?;
+        let registered = registered_window
+            .remove(label)
+            .ok_or_else(|| Error::WindowNotRegistered { label: String::from(label) })?;
+        let no_window_left = registered_window.is_empty();
+        std::mem::drop(registered_window);
+        registered.active.store(false, Ordering::SeqCst);
+        unregister_shortcut(app_handle, &registered.config.shortcut)?;
+        if no_window_left {
+            unregister_close_shortcut(app_handle)?;
+        }
+        Ok(registered.config)
+    }
+
+    /// Returns the labels of the registered windows, sorted.
+    pub fn registered_windows(&self) -> Result<Vec<String>, Error> {
+        let mut labels: Vec<String> = self
+            .registered_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?
+            .keys()
+            .cloned()
+            .collect();
+        labels.sort();
+        Ok(labels)
+    }
+
+    /// Returns the tracked visibility of a registered window.
+    pub fn visibility(&self, label: &str) -> Result<Visibility, Error> {
+        self.registered_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?
+            .get(label)
+            .map(|registered| registered.visibility)
+            .ok_or_else(|| Error::WindowNotRegistered { label: String::from(label) })
+    }
+
+    pub fn show<R: Runtime>(&self, window: &Window<R>) -> Result<(), Error> {
+        self.request(window, Request::Show, Reason::Programmatic)
+    }
+
+    pub fn hide<R: Runtime>(&self, window: &Window<R>) -> Result<(), Error> {
+        self.request(window, Request::Hide, Reason::Programmatic)
+    }
+
+    pub fn toggle<R: Runtime>(&self, window: &Window<R>) -> Result<(), Error> {
+        self.request(window, Request::Toggle, Reason::Programmatic)
+    }
+
+    /// Moves the window through its visibility states. The lock is not held while the
+    /// window is shown or hidden, so concurrent requests see the transitional state and
+    /// are ignored instead of undoing the transition.
+    pub(crate) fn request<R: Runtime>(&self, window: &Window<R>, request: Request, reason: Reason) -> Result<(), Error> {
+        let label = window.label();
+        let (transition, position, monitor_selection, last_position, remember_geometry) = {
+            let mut registered_window = self
+                .registered_window
+                .lock()
+                .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?;
+            let registered = registered_window
+                .get_mut(label)
+                .ok_or_else(|| Error::WindowNotRegistered { label: String::from(label) })?;
+            let transition = match registered.visibility.begin(request) {
+                Some(transition) => transition,
+                None => return Ok(()),
+            };
+            registered.visibility = transition;
+            (
+                transition,
+                registered.config.position.unwrap_or_default(),
+                registered.config.monitor.clone().unwrap_or_default(),
+                registered.last_position,
+                registered.config.remember_geometry.unwrap_or(false),
+            )
+        };
+        let (starting, completed) = match transition {
+            Visibility::Showing => (SpotlightEventKind::WillShow, SpotlightEventKind::Shown),
+            _ => (SpotlightEventKind::WillHide, SpotlightEventKind::Hidden),
+        };
+        let hooks = match transition {
+            Visibility::Showing => &self.before_show,
+            _ => &self.before_hide,
+        };
+        if !hooks.allow(label, reason) {
+            self.set_visibility(label, transition.revert())?;
+            return Ok(());
+        }
+        let will_show_ack = match transition {
+            Visibility::Showing => self.will_show_acks.expect(label),
+            _ => None,
+        };
+        self.event_listeners.emit(window, starting, reason);
+        let result = match transition {
+            Visibility::Showing => {
+                CurrentPlatform::will_show(window);
+                let saved_frames = if remember_geometry {
+                    Some(self.saved_geometry.frames_or_report(&window.app_handle(), label))
+                } else {
+                    None
+                };
+                // placed while still hidden, so that it does not jump once shown
+                let positioned =
+                    CurrentPlatform::position_window(window, &position, &monitor_selection, last_position, saved_frames.as_ref());
+                match (positioned, will_show_ack) {
+                    (Ok(()), Some((sequence, will_show_ack))) => {
+                        let _ = window.eval(&will_show_ack_script(sequence));
+                        show_when_acknowledged(window, will_show_ack, reason);
+                        return Ok(());
+                    }
+                    (positioned, _) => positioned.and_then(|_| CurrentPlatform::show_window(window)),
+                }
+            }
+            _ => {
+                // recorded here, geometry events are ignored once the window is hiding
+                self.saved_geometry.remember_or_report(
+                    &window.app_handle(),
+                    remember_geometry,
+                    || self.save_last_position(window),
+                    || self.record_geometry(window),
+                );
+                let hidden = CurrentPlatform::hide_window(window);
+                if hidden.is_ok() {
+                    CurrentPlatform::did_hide(window, reason);
+                }
+                hidden
+            }
+        };
+        self.complete(window, transition, completed, reason, result)
+    }
+
+    /// Ends the transition with the outcome of showing or hiding the window.
+    fn complete<R: Runtime>(
+        &self,
+        window: &Window<R>,
+        transition: Visibility,
+        completed: SpotlightEventKind,
+        reason: Reason,
+        result: Result<(), Error>,
+    ) -> Result<(), Error> {
+        let visibility = if result.is_ok() { transition.finish() } else { transition.revert() };
+        self.set_visibility(window.label(), visibility)?;
+        if result.is_ok() {
+            self.event_listeners.emit(window, completed, reason);
+        }
+        result
+    }
+
+    pub(crate) fn will_show_handled(&self, label: &str, sequence: Option<u64>) {
+        self.will_show_acks.acknowledge(label, sequence);
+    }
+
+    /// Stops waiting for the webview of window `label`, which loads a page. The injected script
+    /// announces itself again if the new page can acknowledge `spotlight://will-show`.
+    pub(crate) fn page_loaded(&self, label: &str) {
+        self.will_show_acks.forget(label);
+    }
+
+    fn save_last_position<R: Runtime>(&self, window: &Window<R>) -> Result<(), Error> {
+        let frame = CurrentPlatform::frame(window)?;
+        if let Some(registered) = self
+            .registered_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?
+            .get_mut(window.label())
+        {
+            registered.last_position = Some(Point { x: frame.x, y: frame.y });
+        }
+        Ok(())
+    }
+
+    /// Resizes the window to `height` logical pixels, keeping its top edge in place and the
+    /// window within the work area of its monitor, bounded by its `min_height` and `max_height`.
+    pub fn set_content_height<R: Runtime>(&self, window: &Window<R>, height: f64) -> Result<(), Error> {
+        let config = self
+            .registered_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?
+            .get(window.label())
+            .map(|registered| registered.config.clone())
+            .ok_or_else(|| Error::WindowNotRegistered { label: window.label().to_string() })?;
+        CurrentPlatform::set_content_height(window, height, config.min_height, config.max_height)
+    }
+
+    /// Records the geometry the user gave the window, if it remembers its geometry. Moves and
+    /// resizes while the window is shown or hidden come from the plugin and are ignored, the
+    /// hide path records the final geometry itself.
+    fn geometry_changed<R: Runtime>(&self, window: &Window<R>) -> Result<(), Error> {
+        let remember = self
+            .registered_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?
+            .get(window.label())
+            .map(|registered| registered.config.remember_geometry.unwrap_or(false) && registered.visibility == Visibility::Visible)
+            .unwrap_or(false);
+        if !remember {
+            return Ok(());
+        }
+        self.record_geometry(window)
+    }
+
+    /// Records the frame of the window relative to the monitor it is on.
+    fn record_geometry<R: Runtime>(&self, window: &Window<R>) -> Result<(), Error> {
+        let frame = CurrentPlatform::frame(window)?;
+        let center = Point { x: frame.x + frame.width / 2.0, y: frame.y + frame.height / 2.0 };
+        let (key, monitor_frame) = match CurrentPlatform::monitor_at(window, center)? {
+            Some(monitor) => monitor,
+            None => return Ok(()),
+        };
+        let relative = Rect { x: frame.x - monitor_frame.x, y: frame.y - monitor_frame.y, ..frame };
+        self.saved_geometry.record(&window.app_handle(), window.label(), key, relative)
+    }
+
+    /// Ends a transition, unless the window was unregistered in the meantime.
+    fn set_visibility(&self, label: &str, visibility: Visibility) -> Result<(), Error> {
+        if let Some(registered) = self
+            .registered_window
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock registered window")))?
+            .get_mut(label)
+        {
+            registered.visibility = visibility;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn monitor_rect(monitor: &Monitor) -> Rect {
+    Rect {
+        x: monitor.position().x as f64,
+        y: monitor.position().y as f64,
+        width: monitor.size().width as f64,
+        height: monitor.size().height as f64,
+    }
+}
+
+/// Returns the frame of the focused window of the app, other than `window`.
+fn focused_window_rect<R: Runtime>(window: &Window<R>) -> Result<Option<Rect>, Error> {
+    let focused = window
+        .app_handle()
+        .windows()
+        .into_values()
+        .find(|other| other.label() != window.label() && other.is_focused().unwrap_or(false));
+    let focused = match focused {
+        Some(focused) => focused,
+        None => return Ok(None),
+    };
+    let position = focused.outer_position()?;
+    let size = focused.outer_size()?;
+    Ok(Some(Rect {
+        x: position.x as f64,
+        y: position.y as f64,
+        width: size.width as f64,
+        height: size.height as f64,
+    }))
+}
+
+/// Shows the window once its webview handled `spotlight://will-show`, or after a timeout.
+/// Waits on another thread, since the webview is served by the main thread.
+fn show_when_acknowledged<R: Runtime>(window: &Window<R>, will_show_ack: Receiver<()>, reason: Reason) {
+    let window = window.clone();
+    std::thread::spawn(move || {
+        let _ = will_show_ack.recv_timeout(WILL_SHOW_TIMEOUT);
+        let window_ = window.clone();
+        // AppKit may only be used on the main thread
+        let scheduled = window.run_on_main_thread(move || {
+            let result = CurrentPlatform::show_window(&window_);
+            complete_show(&window_, reason, result);
+        });
+        if scheduled.is_err() {
+            let label = window.label().to_string();
+            complete_show(&window, reason, Err(Error::FailedToShowWindow { label }));
+        }
+    });
+}
+
+fn complete_show<R: Runtime>(window: &Window<R>, reason: Reason, result: Result<(), Error>) {
+    let app_handle = window.app_handle();
+    let manager = app_handle.state::<SpotlightManager>();
+    if let Err(err) = manager.complete(window, Visibility::Showing, SpotlightEventKind::Shown, reason, result) {
+        report_error(&app_handle, err);
+    }
+}
+
+fn register_shortcut_for_window<R: Runtime>(window: &Window<R>, window_config: &WindowConfig) -> Result<(), Error> {
+    let app_handle = window.app_handle();
+    let label = window.label().to_string();
+    let shortcut_backend = app_handle.state::<SpotlightManager>().shortcut_backend(&app_handle);
+    if shortcut_backend.is_registered(&window_config.shortcut).unwrap_or(false) {
+        return Err(Error::ShortcutAlreadyRegistered { shortcut: window_config.shortcut.to_string() });
+    }
+    shortcut_backend.register(&window_config.shortcut, Box::new(move |state| {
+        if state != ShortcutState::Pressed {
+            return;
+        }
+        if let Err(err) = toggle_window(&app_handle, &label) {
+            report_error(&app_handle, err);
+        }
+    }))
+}
+
+fn toggle_window<R: Runtime>(app_handle: &AppHandle<R>, label: &str) -> Result<(), Error> {
+    let window = app_handle
+        .get_window(label)
+        .ok_or_else(|| Error::WindowNotFound { label: String::from(label) })?;
+    app_handle.state::<SpotlightManager>().request(&window, Request::Toggle, Reason::Shortcut)
+}
+
+/// Hides every registered window. A window that fails to hide is reported, and does not keep
+/// the others open.
+fn hide_registered_windows<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), Error> {
+    let state = app_handle.state::<SpotlightManager>();
+    for label in state.registered_windows()? {
+        if let Some(window) = app_handle.get_window(&label) {
+            if let Err(err) = state.request(&window, Request::Hide, Reason::CloseShortcut) {
+                report_error(app_handle, err);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn unregister_shortcut<R: Runtime>(app_handle: &AppHandle<R>, shortcut: &Shortcut) -> Result<(), Error> {
+    app_handle.state::<SpotlightManager>().shortcut_backend(app_handle).unregister(shortcut)
+}
+
+fn register_close_shortcut<R: Runtime>(window: &Window<R>) -> Result<(), Error> {
+    let app_handle = window.app_handle();
+    let manager = app_handle.state::<SpotlightManager>();
+    let shortcut_backend = manager.shortcut_backend(&app_handle);
+    if let Some(close_shortcut) = manager.config.grabbed_close_shortcut() {
+        if let Ok(registered) = shortcut_backend.is_registered(close_shortcut) {
+            if !registered {
+                let app_handle = app_handle.clone();
+                shortcut_backend.register(close_shortcut, Box::new(move |state| {
+                    if state != ShortcutState::Pressed {
+                        return;
+                    }
+                    if let Err(err) = hide_registered_windows(&app_handle) {
+                        report_error(&app_handle, err);
+                    }
+                }))?;
+            }
+        } else {
+            return Err(Error::FailedToRegisterShortcut { shortcut: close_shortcut.to_string() });
+        }
+    }
+    Ok(())
+}
+
+fn unregister_close_shortcut<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), Error> {
+    let manager = app_handle.state::<SpotlightManager>();
+    let shortcut_backend = manager.shortcut_backend(app_handle);
+    if let Some(close_shortcut) = manager.config.grabbed_close_shortcut() {
+        if let Ok(registered) = shortcut_backend.is_registered(close_shortcut) {
+            if registered {
+                shortcut_backend.unregister(close_shortcut)?;
+            }
+        } else {
+            return Err(Error::FailedToUnregisterShortcut { shortcut: close_shortcut.to_string() });
+        }
+    }
+    Ok(())
+}
+
+fn handle_focus_state_change<R: Runtime>(window: &Window<R>, active: Arc<AtomicBool>) {
+    let w = window.to_owned();
+    window.on_window_event(move |event| {
+        if !active.load(Ordering::SeqCst) {
+            return;
+        }
+        if let Err(err) = handle_window_event(&w, event) {
+            report_error(&w.app_handle(), err);
+        }
+    });
+}
+
+fn handle_window_event<R: Runtime>(window: &Window<R>, event: &WindowEvent) -> Result<(), Error> {
+    match event {
+        WindowEvent::Focused(false) => {
+            let app_handle = window.app_handle();
+            unregister_close_shortcut(&app_handle)?;
+            app_handle.state::<SpotlightManager>().request(window, Request::Hide, Reason::Blur)?;
+        }
+        WindowEvent::Focused(true) => register_close_shortcut(window)?,
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+            window.app_handle().state::<SpotlightManager>().geometry_changed(window)?;
+        }
+        WindowEvent::Destroyed => {
+            let app_handle = window.app_handle();
+            let manager = app_handle.state::<SpotlightManager>();
+            manager.handle_window_destroyed(&app_handle, window.label())?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, time::Duration};
+    use tauri::{
+        test::{mock_builder, mock_context, noop_assets, MockRuntime},
+        App, GlobalShortcutManager, Manager, WindowEvent,
+    };
+    use super::{handle_window_event, hide_registered_windows, toggle_window};
+    use crate::{
+        CloseShortcutMode, Error, FakeShortcutBackend, ManagerExt, PluginConfig, Reason, Shortcut, ShortcutBackend,
+        ShortcutHandler, ShortcutOverrides, SpotlightEventKind, Visibility, WindowConfig,
+    };
+    use crate::error::report_error;
+    use crate::overrides::shortcuts_path;
+
+    fn create_app(config: PluginConfig) -> App<MockRuntime> {
+        mock_builder()
+            .plugin(crate::init(Some(config)))
+            .build(mock_context(noop_assets()))
+            .unwrap()
+    }
+
+    fn create_app_with_shortcuts<B: ShortcutBackend + 'static>(config: PluginConfig, shortcuts: B) -> App<MockRuntime> {
+        mock_builder()
+            .plugin(crate::init_with_shortcut_backend(Some(config), shortcuts))
+            .build(mock_context(noop_assets()))
+            .unwrap()
+    }
+
+    /// Refuses every registration once `failing` is set.
+    #[derive(Clone, Default)]
+    struct FailingShortcutBackend {
+        shortcuts: FakeShortcutBackend,
+        failing: Arc<AtomicBool>,
+    }
+
+    impl ShortcutBackend for FailingShortcutBackend {
+        fn register(&self, shortcut: &Shortcut, handler: ShortcutHandler) -> Result<(), Error> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(Error::FailedToRegisterShortcut { shortcut: shortcut.to_string() });
+            }
+            self.shortcuts.register(shortcut, handler)
+        }
+
+        fn unregister(&self, shortcut: &Shortcut) -> Result<(), Error> {
+            self.shortcuts.unregister(shortcut)
+        }
+
+        fn is_registered(&self, shortcut: &Shortcut) -> Result<bool, Error> {
+            self.shortcuts.is_registered(shortcut)
+        }
+    }
+
+    fn registered_labels(app: &App<MockRuntime>) -> Vec<String> {
+        let mut labels: Vec<String> = app.spotlight().registered_window.lock().unwrap().keys().cloned().collect();
+        labels.sort();
+        labels
+    }
+
+    fn main_window_config() -> WindowConfig {
+        WindowConfig::for_test("main", "Ctrl+Shift+J")
+    }
+
+    fn config_with_main_window() -> PluginConfig {
+        PluginConfig {
+            windows: Some(vec![main_window_config()]),
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn registers_shortcut_for_configured_window() {
+        let app = create_app(config_with_main_window());
+        let shortcut_manager = app.global_shortcut_manager();
+        assert!(shortcut_manager.is_registered("Control+Shift+J").unwrap());
+        assert!(shortcut_manager.is_registered("Escape").unwrap());
+        assert_eq!(registered_labels(&app), vec![String::from("main")]);
+    }
+
+    #[test]
+    fn ignores_windows_without_config() {
+        let app = create_app(PluginConfig {
+            windows: None,
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            ..Default::default()
+        });
+        let shortcut_manager = app.global_shortcut_manager();
+        assert!(!shortcut_manager.is_registered("Escape").unwrap());
+        assert!(registered_labels(&app).is_empty());
+    }
+
+    #[test]
+    fn init_spotlight_window_is_idempotent() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        app.spotlight().init_spotlight_window(&window).unwrap();
+        assert_eq!(registered_labels(&app), vec![String::from("main")]);
+    }
+
+    #[test]
+    fn register_window_at_runtime() {
+        let app = create_app(PluginConfig {
+            windows: None,
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            ..Default::default()
+        });
+        let window = app.get_window("main").unwrap();
+        app.spotlight().register_window(&window, main_window_config()).unwrap();
+        assert_eq!(registered_labels(&app), vec![String::from("main")]);
+        assert!(app.global_shortcut_manager().is_registered("Control+Shift+J").unwrap());
+
+        let err = app.spotlight().register_window(&window, main_window_config()).unwrap_err();
+        assert!(matches!(err, Error::WindowAlreadyRegistered { label } if label == "main"));
+    }
+
+    #[test]
+    fn register_window_applies_stored_shortcut() {
+        let app = create_app(PluginConfig {
+            windows: None,
+            persist_shortcuts: Some(true),
+            ..Default::default()
+        });
+        let path = shortcuts_path(&app.handle()).unwrap();
+        let mut overrides = ShortcutOverrides::default();
+        overrides.set("main", "Ctrl+K".parse().unwrap());
+        overrides.save(&path).unwrap();
+        let window = app.get_window("main").unwrap();
+        let registered = app.spotlight().register_window(&window, main_window_config());
+        fs::remove_file(&path).unwrap();
+        registered.unwrap();
+        let shortcut_manager = app.global_shortcut_manager();
+        assert!(shortcut_manager.is_registered("Control+K").unwrap());
+        assert!(!shortcut_manager.is_registered("Control+Shift+J").unwrap());
+    }
+
+    #[test]
+    fn unregister_window_releases_shortcuts() {
+        let app = create_app(config_with_main_window());
+        app.spotlight().unregister_window(&app.handle(), "main").unwrap();
+        let shortcut_manager = app.global_shortcut_manager();
+        assert!(registered_labels(&app).is_empty());
+        assert!(!shortcut_manager.is_registered("Control+Shift+J").unwrap());
+        assert!(!shortcut_manager.is_registered("Escape").unwrap());
+
+        let err = app.spotlight().unregister_window(&app.handle(), "main").unwrap_err();
+        assert!(matches!(err, Error::WindowNotRegistered { label } if label == "main"));
+    }
+
+    #[test]
+    fn set_shortcut_rebinds_window() {
+        let app = create_app(config_with_main_window());
+        let shortcut_manager = app.global_shortcut_manager();
+        app.spotlight().set_shortcut(&app.handle(), "main", "Ctrl+K".parse().unwrap()).unwrap();
+        assert!(!shortcut_manager.is_registered("Control+Shift+J").unwrap());
+        assert!(shortcut_manager.is_registered("Control+K").unwrap());
+    }
+
+    #[test]
+    fn set_shortcut_rolls_back_on_failure() {
+        let app = create_app(config_with_main_window());
+        let mut shortcut_manager = app.global_shortcut_manager();
+        shortcut_manager.register("Alt+X", || {}).unwrap();
+        let err = app.spotlight().set_shortcut(&app.handle(), "main", "Alt+X".parse().unwrap()).unwrap_err();
+        assert!(matches!(err, Error::ShortcutAlreadyRegistered { shortcut } if shortcut == "Alt+X"));
+        assert!(shortcut_manager.is_registered("Control+Shift+J").unwrap());
+    }
+
+    #[test]
+    fn set_shortcut_returns_error_of_new_shortcut() {
+        let shortcuts = FailingShortcutBackend::default();
+        let app = create_app_with_shortcuts(config_with_main_window(), shortcuts.clone());
+        let reported = Arc::new(Mutex::new(vec![]));
+        let reported_ = reported.clone();
+        app.spotlight().set_error_handler(move |err| {
+            reported_.lock().unwrap().push(matches!(err, Error::FailedToRegisterShortcut { shortcut } if shortcut == "Control+Shift+J"));
+        });
+        shortcuts.failing.store(true, Ordering::SeqCst);
+        let err = app.spotlight().set_shortcut(&app.handle(), "main", "Alt+X".parse().unwrap()).unwrap_err();
+        assert!(matches!(err, Error::FailedToRegisterShortcut { shortcut } if shortcut == "Alt+X"));
+        // the failed rollback is reported instead
+        assert_eq!(*reported.lock().unwrap(), vec![true]);
+    }
+
+    #[test]
+    fn set_shortcut_rejects_conflicts() {
+        let app = create_app(config_with_main_window());
+        let shortcut_manager = app.global_shortcut_manager();
+        let err = app.spotlight().set_shortcut(&app.handle(), "main", "Esc".parse().unwrap()).unwrap_err();
+        assert!(matches!(err, Error::ShortcutConflict(_)));
+        assert!(shortcut_manager.is_registered("Control+Shift+J").unwrap());
+    }
+
+    #[test]
+    fn register_window_rejects_conflicts() {
+        let app = create_app(PluginConfig {
+            global_close_shortcut: Some("Escape".parse().unwrap()),
+            ..Default::default()
+        });
+        let window = app.get_window("main").unwrap();
+        let window_config = WindowConfig {
+            shortcut: "Esc".parse().unwrap(),
+            ..main_window_config()
+        };
+        let err = app.spotlight().register_window(&window, window_config).unwrap_err();
+        assert!(matches!(err, Error::ShortcutConflict(_)));
+        assert!(registered_labels(&app).is_empty());
+    }
+
+    #[test]
+    fn setup_fails_on_conflicting_config() {
+        let config = PluginConfig {
+            windows: Some(vec![
+                main_window_config(),
+                WindowConfig::for_test("other", "Control+Shift+J"),
+            ]),
+            ..Default::default()
+        };
+        let result = mock_builder()
+            .plugin(crate::init(Some(config)))
+            .build(mock_context(noop_assets()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn destroyed_window_is_rewired() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        let shortcut_manager = app.global_shortcut_manager();
+
+        handle_window_event(&window, &WindowEvent::Destroyed).unwrap();
+        assert!(registered_labels(&app).is_empty());
+        assert!(!shortcut_manager.is_registered("Control+Shift+J").unwrap());
+
+        app.spotlight().init_spotlight_window(&window).unwrap();
+        assert_eq!(registered_labels(&app), vec![String::from("main")]);
+        assert!(shortcut_manager.is_registered("Control+Shift+J").unwrap());
+    }
+
+    #[test]
+    fn destroyed_runtime_window_is_rewired() {
+        let app = create_app(PluginConfig::default());
+        let window = app.get_window("main").unwrap();
+        app.spotlight().register_window(&window, main_window_config()).unwrap();
+
+        handle_window_event(&window, &WindowEvent::Destroyed).unwrap();
+        assert!(registered_labels(&app).is_empty());
+
+        app.spotlight().init_spotlight_window(&window).unwrap();
+        assert_eq!(registered_labels(&app), vec![String::from("main")]);
+        assert!(app.global_shortcut_manager().is_registered("Control+Shift+J").unwrap());
+    }
+
+    #[test]
+    fn show_and_hide_window() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        app.spotlight().hide(&window).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hidden);
+        app.spotlight().show(&window).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Visible);
+    }
+
+    #[test]
+    fn failing_geometry_store_does_not_block_transitions() {
+        let app = create_app(PluginConfig {
+            windows: Some(vec![WindowConfig { remember_geometry: Some(true), ..main_window_config() }]),
+            ..config_with_main_window()
+        });
+        let window = app.get_window("main").unwrap();
+        let reported = Arc::new(Mutex::new(vec![]));
+        let reported_ = reported.clone();
+        app.spotlight().set_error_handler(move |err| {
+            reported_.lock().unwrap().push(err.code());
+        });
+        app.spotlight().saved_geometry.poison();
+
+        app.spotlight().hide(&window).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hidden);
+        app.spotlight().show(&window).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Visible);
+        assert!(reported.lock().unwrap().contains(&"mutex"));
+    }
+
+    #[test]
+    fn toggle_follows_tracked_visibility() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        app.spotlight().toggle(&window).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hidden);
+        app.spotlight().toggle(&window).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Visible);
+    }
+
+    #[test]
+    fn blur_and_close_shortcut_go_through_manager() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        handle_window_event(&window, &WindowEvent::Focused(false)).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hidden);
+
+        app.spotlight().show(&window).unwrap();
+        hide_registered_windows(&app.handle()).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hidden);
+    }
+
+    #[test]
+    fn emits_lifecycle_events() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        let events = Arc::new(Mutex::new(vec![]));
+        let events_ = events.clone();
+        app.spotlight().on_event(move |event| {
+            events_.lock().unwrap().push((event.kind, event.label.clone(), event.reason));
+        });
+
+        handle_window_event(&window, &WindowEvent::Focused(false)).unwrap();
+        app.spotlight().hide(&window).unwrap();
+        toggle_window(&app.handle(), "main").unwrap();
+        assert_eq!(*events.lock().unwrap(), vec![
+            (SpotlightEventKind::WillHide, String::from("main"), Reason::Blur),
+            (SpotlightEventKind::Hidden, String::from("main"), Reason::Blur),
+            (SpotlightEventKind::WillShow, String::from("main"), Reason::Shortcut),
+            (SpotlightEventKind::Shown, String::from("main"), Reason::Shortcut),
+        ]);
+    }
+
+    #[test]
+    fn show_waits_for_webview() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        app.spotlight().on_event(move |event| {
+            let _ = tx.lock().unwrap().send(event.kind);
+        });
+        app.spotlight().hide(&window).unwrap();
+        assert_eq!(rx.try_iter().count(), 2);
+
+        // sent by the injected script when the page loads
+        app.spotlight().will_show_handled("main", None);
+        app.spotlight().show(&window).unwrap();
+        assert_eq!(rx.try_recv().unwrap(), SpotlightEventKind::WillShow);
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Showing);
+        // the first show the app waits for
+        app.spotlight().will_show_handled("main", Some(1));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), SpotlightEventKind::Shown);
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Visible);
+    }
+
+    #[test]
+    fn hooks_veto_transitions() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        let events = Arc::new(Mutex::new(vec![]));
+        let events_ = events.clone();
+        app.spotlight().on_event(move |event| events_.lock().unwrap().push(event.kind));
+        app.spotlight().before_hide(|label, reason| !(label == "main" && reason == Reason::Blur));
+
+        handle_window_event(&window, &WindowEvent::Focused(false)).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Visible);
+        assert!(events.lock().unwrap().is_empty());
+
+        app.spotlight().hide(&window).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hidden);
+
+        app.spotlight().before_show(|_, _| false);
+        toggle_window(&app.handle(), "main").unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hidden);
+    }
+
+    #[test]
+    fn requests_during_transition_are_ignored() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        app.spotlight().registered_window.lock().unwrap().get_mut("main").unwrap().visibility = Visibility::Hiding;
+        app.spotlight().toggle(&window).unwrap();
+        app.spotlight().show(&window).unwrap();
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hiding);
+    }
+
+    #[test]
+    fn show_unregistered_window() {
+        let app = create_app(PluginConfig::default());
+        let window = app.get_window("main").unwrap();
+        let err = app.spotlight().show(&window).unwrap_err();
+        assert!(matches!(err, Error::WindowNotRegistered { label } if label == "main"));
+    }
+
+    #[test]
+    fn reports_errors_to_handler() {
+        let app = create_app(config_with_main_window());
+        let reported = Arc::new(Mutex::new(vec![]));
+        let reported_ = reported.clone();
+        app.spotlight().set_error_handler(move |err| {
+            reported_.lock().unwrap().push(err.code());
+        });
+        report_error(&app.handle(), Error::WindowNotRegistered { label: String::from("foo") });
+        assert_eq!(*reported.lock().unwrap(), vec!["window_not_registered"]);
+    }
+
+    #[test]
+    fn close_shortcut_follows_focus() {
+        let app = create_app(config_with_main_window());
+        let window = app.get_window("main").unwrap();
+        let shortcut_manager = app.global_shortcut_manager();
+
+        handle_window_event(&window, &WindowEvent::Focused(false)).unwrap();
+        assert!(!shortcut_manager.is_registered("Escape").unwrap());
+
+        handle_window_event(&window, &WindowEvent::Focused(true)).unwrap();
+        assert!(shortcut_manager.is_registered("Escape").unwrap());
+    }
+
+    #[test]
+    fn local_close_shortcut_is_not_grabbed() {
+        let app = create_app(PluginConfig {
+            close_shortcut_mode: Some(CloseShortcutMode::Local),
+            ..config_with_main_window()
+        });
+        let window = app.get_window("main").unwrap();
+        let shortcut_manager = app.global_shortcut_manager();
+        assert!(!shortcut_manager.is_registered("Escape").unwrap());
+
+        handle_window_event(&window, &WindowEvent::Focused(true)).unwrap();
+        assert!(!shortcut_manager.is_registered("Escape").unwrap());
+        assert_eq!(app.spotlight().config.local_close_shortcut(), Some(&"Escape".parse().unwrap()));
+    }
+
+    #[test]
+    fn fake_shortcut_presses_toggle_window() {
+        let shortcuts = FakeShortcutBackend::new();
+        let app = create_app_with_shortcuts(config_with_main_window(), shortcuts.clone());
+        let shortcut: Shortcut = "Ctrl+Shift+J".parse().unwrap();
+        assert_eq!(shortcuts.registered(), vec![shortcut.clone(), "Escape".parse().unwrap()]);
+        assert!(!app.global_shortcut_manager().is_registered("Control+Shift+J").unwrap());
+
+        assert!(shortcuts.release(&shortcut));
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Visible);
+        assert!(shortcuts.press(&shortcut));
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hidden);
+        assert!(shortcuts.press(&shortcut));
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Visible);
+
+        assert!(shortcuts.press(&"Escape".parse().unwrap()));
+        assert_eq!(app.spotlight().visibility("main").unwrap(), Visibility::Hidden);
+    }
+}
@@ -0,0 +1,116 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+use tauri::{AppHandle, Runtime};
+use crate::{Error, PluginConfig, Shortcut};
+
+const SHORTCUTS_FILE_NAME: &str = "spotlight-shortcuts.json";
+
+/// Shortcuts chosen by the user at runtime, keyed by window label.
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct ShortcutOverrides(HashMap<String, Shortcut>);
+
+impl ShortcutOverrides {
+    /// Reads the overrides from `path`, a missing file yields no overrides.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, label: &str) -> Option<&Shortcut> {
+        self.0.get(label)
+    }
+
+    pub fn set(&mut self, label: &str, shortcut: Shortcut) {
+        self.0.insert(String::from(label), shortcut);
+    }
+
+    /// Replaces the shortcuts of the configured windows with the overridden ones.
+    pub fn apply(&self, config: &mut PluginConfig) {
+        if let Some(windows) = config.windows.as_mut() {
+            for window in windows {
+                if let Some(shortcut) = self.get(&window.label) {
+                    window.shortcut = shortcut.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Location of the overrides file in the app data directory.
+pub(crate) fn shortcuts_path<R: Runtime>(app_handle: &AppHandle<R>) -> Result<PathBuf, Error> {
+    app_handle
+        .path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(SHORTCUTS_FILE_NAME))
+        .ok_or_else(|| Error::Other(String::from("failed to resolve app data dir")))
+}
+
+/// Returns the shortcut recorded for the window if persisting shortcuts is enabled.
+pub(crate) fn load_shortcut<R: Runtime>(app_handle: &AppHandle<R>, config: &PluginConfig, label: &str) -> Result<Option<Shortcut>, Error> {
+    if !config.persist_shortcuts.unwrap_or(false) {
+        return Ok(None);
+    }
+    let overrides = ShortcutOverrides::load(&shortcuts_path(app_handle)?)?;
+    Ok(overrides.get(label).cloned())
+}
+
+/// Records `shortcut` for the window if persisting shortcuts is enabled.
+pub(crate) fn save_shortcut<R: Runtime>(app_handle: &AppHandle<R>, config: &PluginConfig, label: &str, shortcut: Shortcut) -> Result<(), Error> {
+    if !config.persist_shortcuts.unwrap_or(false) {
+        return Ok(());
+    }
+    let path = shortcuts_path(app_handle)?;
+    let mut overrides = ShortcutOverrides::load(&path)?;
+    overrides.set(label, shortcut);
+    overrides.save(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use super::ShortcutOverrides;
+    use crate::{PluginConfig, WindowConfig};
+
+    #[test]
+    fn load_missing_file() {
+        let path = env::temp_dir().join("spotlight-overrides-missing").join("shortcuts.json");
+        assert_eq!(ShortcutOverrides::load(&path).unwrap(), ShortcutOverrides::default());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = env::temp_dir().join("spotlight-overrides-save");
+        let path = dir.join("shortcuts.json");
+        let mut overrides = ShortcutOverrides::default();
+        overrides.set("main", "Ctrl+K".parse().unwrap());
+        overrides.save(&path).unwrap();
+        assert_eq!(ShortcutOverrides::load(&path).unwrap(), overrides);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn apply_overrides() {
+        let mut overrides = ShortcutOverrides::default();
+        overrides.set("main", "Ctrl+K".parse().unwrap());
+        overrides.set("foo", "Ctrl+L".parse().unwrap());
+        let mut config = PluginConfig {
+            windows: Some(vec![
+                WindowConfig::for_test("main", "Ctrl+I"),
+            ]),
+            ..Default::default()
+        };
+        overrides.apply(&mut config);
+        assert_eq!(config.windows.unwrap()[0].shortcut.to_string(), "Control+K");
+    }
+}
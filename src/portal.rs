@@ -0,0 +1,676 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    sync::{atomic::{AtomicU64, Ordering}, mpsc::channel, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use gio::{
+    glib::{self, ToVariant, Variant, VariantTy},
+    BusType, Cancellable, DBusCallFlags, DBusConnection, DBusSignalFlags,
+};
+use tauri::{AppHandle, Runtime};
+use crate::{Error, Modifier, Shortcut, ShortcutBackend, ShortcutHandler, ShortcutState};
+use crate::error::report_error;
+use crate::shortcut_backend::SharedShortcutHandler;
+
+pub(crate) const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
+pub(crate) const PORTAL_OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+pub(crate) const GLOBAL_SHORTCUTS_INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+/// Shown by the portal next to each shortcut when the user is asked to confirm them
+const SHORTCUT_DESCRIPTION: &str = "Show or hide a spotlight window";
+/// How long a request may wait for its response, long enough for the user to confirm shortcuts
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Default)]
+struct PortalState {
+    /// Handlers keyed by shortcut id, the shortcut as displayed by `Shortcut`
+    handlers: HashMap<String, (Shortcut, SharedShortcutHandler)>,
+    /// Triggers chosen by the user, keyed by shortcut id
+    triggers: HashMap<String, String>,
+    /// Handle of the session the shortcuts are bound to
+    session: Option<String>,
+    /// Ids of the shortcuts bound to the session, which stay bound once unregistered
+    bound: HashSet<String>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<PortalState>,
+    /// Makes the tokens of requests and sessions unique
+    tokens: AtomicU64,
+}
+
+impl Shared {
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, PortalState>, Error> {
+        self.state
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock portal state")))
+    }
+}
+
+/// Global shortcuts of the `org.freedesktop.portal.GlobalShortcuts` portal, which works under
+/// Wayland where Tauri can not grab keys.
+///
+/// All shortcuts are bound to a single session, created with the first one. Shortcuts
+/// registered later are bound to it in the background, where the portal may ask the user to
+/// confirm them. Unregistered shortcuts stay bound but are ignored, so registering them again
+/// doesn't go through the portal.
+pub(crate) struct PortalShortcutBackend<R: Runtime> {
+    app_handle: AppHandle<R>,
+    connection: DBusConnection,
+    shared: Arc<Shared>,
+    /// Context of the thread talking to the portal
+    context: glib::MainContext,
+}
+
+impl<R: Runtime> PortalShortcutBackend<R> {
+    /// Connects to the portal of the session bus, only in Wayland sessions.
+    pub(crate) fn connect(app_handle: AppHandle<R>) -> Result<Self, Error> {
+        if env::var_os("WAYLAND_DISPLAY").is_none() {
+            return Err(Error::Other(String::from("not a Wayland session")));
+        }
+        let connection = gio::bus_get_sync(BusType::Session, None::<&Cancellable>).map_err(glib_error)?;
+        Self::with_connection(app_handle, connection)
+    }
+
+    /// Uses the portal on `connection`, failing if it does not provide global shortcuts.
+    pub(crate) fn with_connection(app_handle: AppHandle<R>, connection: DBusConnection) -> Result<Self, Error> {
+        connection
+            .call_sync(
+                Some(PORTAL_BUS_NAME),
+                PORTAL_OBJECT_PATH,
+                "org.freedesktop.DBus.Properties",
+                "Get",
+                Some(&(GLOBAL_SHORTCUTS_INTERFACE, "version").to_variant()),
+                None,
+                DBusCallFlags::NONE,
+                -1,
+                None::<&Cancellable>,
+            )
+            .map_err(glib_error)?;
+        let shared = Arc::new(Shared::default());
+        let context = glib::MainContext::new();
+        let (ready_tx, ready_rx) = channel();
+        let worker_context = context.clone();
+        let worker_connection = connection.clone();
+        let worker_shared = shared.clone();
+        let worker_app_handle = app_handle.clone();
+        thread::spawn(move || {
+            let main_loop = glib::MainLoop::new(Some(&worker_context), false);
+            let _ = worker_context.with_thread_default(|| {
+                subscribe(&worker_app_handle, &worker_connection, &worker_shared);
+                let _ = ready_tx.send(());
+                main_loop.run();
+            });
+        });
+        ready_rx
+            .recv()
+            .map_err(|_| Error::Other(String::from("failed to start the portal thread")))?;
+        Ok(Self { app_handle, connection, shared, context })
+    }
+
+    /// Binds the shortcuts that are not bound yet on the portal thread.
+    fn schedule_bind(&self) {
+        let app_handle = self.app_handle.clone();
+        let connection = self.connection.clone();
+        let shared = self.shared.clone();
+        self.context.invoke(move || {
+            if let Err(err) = bind(&connection, &shared) {
+                report_error(&app_handle, err);
+            }
+        });
+    }
+}
+
+impl<R: Runtime> ShortcutBackend for PortalShortcutBackend<R> {
+    fn register(&self, shortcut: &Shortcut, handler: ShortcutHandler) -> Result<(), Error> {
+        let id = shortcut.to_string();
+        {
+            let mut state = self.shared.lock()?;
+            if state.handlers.contains_key(&id) {
+                return Err(Error::ShortcutAlreadyRegistered { shortcut: id });
+            }
+            state.handlers.insert(id.clone(), (shortcut.clone(), Arc::from(handler)));
+            if state.bound.contains(&id) {
+                return Ok(());
+            }
+        }
+        self.schedule_bind();
+        Ok(())
+    }
+
+    fn unregister(&self, shortcut: &Shortcut) -> Result<(), Error> {
+        let id = shortcut.to_string();
+        if self.shared.lock()?.handlers.remove(&id).is_none() {
+            return Err(Error::FailedToUnregisterShortcut { shortcut: id });
+        }
+        Ok(())
+    }
+
+    fn is_registered(&self, shortcut: &Shortcut) -> Result<bool, Error> {
+        Ok(self.shared.lock()?.handlers.contains_key(&shortcut.to_string()))
+    }
+
+    fn trigger_description(&self, shortcut: &Shortcut) -> Option<String> {
+        let id = shortcut.to_string();
+        let state = self.shared.lock().ok()?;
+        state.handlers.get(&id)?;
+        state.triggers.get(&id).cloned()
+    }
+}
+
+fn glib_error(err: glib::Error) -> Error {
+    Error::Other(err.to_string())
+}
+
+/// Listens to the shortcuts of the current session being pressed, released or changed by the user.
+fn subscribe<R: Runtime>(app_handle: &AppHandle<R>, connection: &DBusConnection, shared: &Arc<Shared>) {
+    for (signal, state) in [("Activated", ShortcutState::Pressed), ("Deactivated", ShortcutState::Released)] {
+        let app_handle = app_handle.clone();
+        let shared = shared.clone();
+        connection.signal_subscribe(
+            Some(PORTAL_BUS_NAME),
+            Some(GLOBAL_SHORTCUTS_INTERFACE),
+            Some(signal),
+            Some(PORTAL_OBJECT_PATH),
+            None,
+            DBusSignalFlags::NONE,
+            move |_, _, _, _, _, parameters| {
+                let session = parameters.child_value(0);
+                let id = parameters.child_value(1);
+                let handler = match shared.lock() {
+                    Ok(portal) if portal.session.as_deref() == session.str() => {
+                        id.str().and_then(|id| portal.handlers.get(id)).map(|(_, handler)| handler.clone())
+                    }
+                    _ => None,
+                };
+                if let Some(handler) = handler {
+                    // shortcuts are handled on the main thread, whatever their backend
+                    let _ = app_handle.run_on_main_thread(move || handler(state));
+                }
+            },
+        );
+    }
+    let shared = shared.clone();
+    connection.signal_subscribe(
+        Some(PORTAL_BUS_NAME),
+        Some(GLOBAL_SHORTCUTS_INTERFACE),
+        Some("ShortcutsChanged"),
+        Some(PORTAL_OBJECT_PATH),
+        None,
+        DBusSignalFlags::NONE,
+        move |_, _, _, _, _, parameters| {
+            if let Ok(mut portal) = shared.lock() {
+                if portal.session.as_deref() == parameters.child_value(0).str() {
+                    portal.triggers = triggers(&parameters.child_value(1));
+                }
+            }
+        },
+    );
+}
+
+/// Binds the registered shortcuts that are not bound yet to the session, creating it first.
+fn bind(connection: &DBusConnection, shared: &Shared) -> Result<(), Error> {
+    let (session, shortcuts) = {
+        let state = shared.lock()?;
+        let shortcuts: Vec<(String, HashMap<String, Variant>)> = state
+            .handlers
+            .iter()
+            .filter(|(id, _)| !state.bound.contains(*id))
+            .map(|(id, (shortcut, _))| {
+                let mut options = HashMap::new();
+                options.insert(String::from("description"), SHORTCUT_DESCRIPTION.to_variant());
+                options.insert(String::from("preferred_trigger"), preferred_trigger(shortcut).to_variant());
+                (id.clone(), options)
+            })
+            .collect();
+        (state.session.clone(), shortcuts)
+    };
+    // the shortcuts were bound by an earlier call
+    if shortcuts.is_empty() {
+        return Ok(());
+    }
+    let session = match session {
+        Some(session) => session,
+        None => {
+            let session = create_session(connection, shared)?;
+            shared.lock()?.session = Some(session.clone());
+            session
+        }
+    };
+    let session_path = object_path(&session)?;
+    let ids: Vec<String> = shortcuts.iter().map(|(id, _)| id.clone()).collect();
+    let shortcuts = shortcuts.to_variant();
+    request(connection, shared, "BindShortcuts", HashMap::new(), REQUEST_TIMEOUT, |options| {
+        Variant::tuple_from_iter([session_path.clone(), shortcuts, "".to_variant(), options])
+    })?;
+    shared.lock()?.bound.extend(ids);
+    let results = request(connection, shared, "ListShortcuts", HashMap::new(), REQUEST_TIMEOUT, |options| {
+        Variant::tuple_from_iter([session_path, options])
+    })?;
+    shared.lock()?.triggers = results.get("shortcuts").map(triggers).unwrap_or_default();
+    Ok(())
+}
+
+fn create_session(connection: &DBusConnection, shared: &Shared) -> Result<String, Error> {
+    let token = format!("spotlight{}", shared.tokens.fetch_add(1, Ordering::SeqCst));
+    let mut options = HashMap::new();
+    options.insert(String::from("session_handle_token"), token.to_variant());
+    let results = request(connection, shared, "CreateSession", options, REQUEST_TIMEOUT, |options| {
+        Variant::tuple_from_iter([options])
+    })?;
+    results
+        .get("session_handle")
+        .and_then(|handle| handle.str().map(String::from))
+        .ok_or_else(|| Error::Other(String::from("the portal did not return a session")))
+}
+
+/// Calls `method` of the portal with the parameters built by `parameters` around `options`,
+/// and waits up to `timeout` for the results of the request. Runs on the portal thread, which
+/// keeps handling signals meanwhile.
+fn request<F: FnOnce(Variant) -> Variant>(
+    connection: &DBusConnection,
+    shared: &Shared,
+    method: &str,
+    mut options: HashMap<String, Variant>,
+    timeout: Duration,
+    parameters: F,
+) -> Result<HashMap<String, Variant>, Error> {
+    let token = format!("spotlight{}", shared.tokens.fetch_add(1, Ordering::SeqCst));
+    let sender = connection
+        .unique_name()
+        .map(|name| name.trim_start_matches(':').replace('.', "_"))
+        .ok_or_else(|| Error::Other(String::from("not connected to a message bus")))?;
+    // the path of the request is known in advance, so that no response is missed
+    let request_path = format!("{}/request/{}/{}", PORTAL_OBJECT_PATH, sender, token);
+    let (tx, rx) = channel();
+    let subscription = connection.signal_subscribe(
+        Some(PORTAL_BUS_NAME),
+        Some(REQUEST_INTERFACE),
+        Some("Response"),
+        Some(&request_path),
+        None,
+        DBusSignalFlags::NONE,
+        move |_, _, _, _, _, parameters| {
+            let _ = tx.send(parameters.get::<(u32, HashMap<String, Variant>)>());
+        },
+    );
+    options.insert(String::from("handle_token"), token.to_variant());
+    let result = connection.call_sync(
+        Some(PORTAL_BUS_NAME),
+        PORTAL_OBJECT_PATH,
+        GLOBAL_SHORTCUTS_INTERFACE,
+        method,
+        Some(&parameters(options.to_variant())),
+        None,
+        DBusCallFlags::NONE,
+        -1,
+        None::<&Cancellable>,
+    );
+    let context = glib::MainContext::ref_thread_default();
+    // wakes the loop below up once the request timed out
+    let wakeup = glib::timeout_source_new(timeout, None, glib::PRIORITY_DEFAULT, || glib::Continue(false));
+    wakeup.attach(Some(&context));
+    let deadline = Instant::now() + timeout;
+    let response = result.map_err(glib_error).and_then(|_| loop {
+        // binding waits for the user to confirm the shortcuts
+        if let Ok(response) = rx.try_recv() {
+            break Ok(response);
+        }
+        if Instant::now() >= deadline {
+            break Err(Error::PortalRequestTimedOut { method: String::from(method) });
+        }
+        context.iteration(true);
+    });
+    wakeup.destroy();
+    connection.signal_unsubscribe(subscription);
+    if let Err(Error::PortalRequestTimedOut { .. }) = response {
+        // the request may still be waiting for the user
+        let _ = connection.call_sync(
+            Some(PORTAL_BUS_NAME),
+            &request_path,
+            REQUEST_INTERFACE,
+            "Close",
+            None,
+            None,
+            DBusCallFlags::NONE,
+            -1,
+            None::<&Cancellable>,
+        );
+    }
+    match response? {
+        Some((0, results)) => Ok(results),
+        Some((code, _)) => Err(Error::Other(format!("the portal denied {} with response {}", method, code))),
+        None => Err(Error::Other(format!("invalid response of the portal to {}", method))),
+    }
+}
+
+fn object_path(path: &str) -> Result<Variant, Error> {
+    Variant::parse(Some(VariantTy::OBJECT_PATH), &format!("'{}'", path)).map_err(glib_error)
+}
+
+/// Reads the `trigger_description` of each shortcut in a list of shortcuts returned by the portal.
+fn triggers(shortcuts: &Variant) -> HashMap<String, String> {
+    shortcuts
+        .get::<Vec<(String, HashMap<String, Variant>)>>()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(id, properties)| {
+            let trigger = properties.get("trigger_description")?.str()?.to_string();
+            Some((id, trigger))
+        })
+        .collect()
+}
+
+/// Formats `shortcut` as a trigger of the XDG shortcuts specification, e.g. `CTRL+SHIFT+j`,
+/// with the key named after its XKB keysym.
+fn preferred_trigger(shortcut: &Shortcut) -> String {
+    let mut trigger = String::new();
+    for modifier in shortcut.resolved_modifiers() {
+        trigger.push_str(match modifier {
+            Modifier::Control | Modifier::CommandOrControl => "CTRL+",
+            Modifier::Alt => "ALT+",
+            Modifier::Shift => "SHIFT+",
+            Modifier::Super => "LOGO+",
+        });
+    }
+    let key = shortcut.key();
+    let keysym = match key {
+        "Backquote" => "grave",
+        "Backslash" | "\\" => "backslash",
+        "BracketLeft" => "bracketleft",
+        "BracketRight" => "bracketright",
+        "Comma" => "comma",
+        "=" => "equal",
+        "-" => "minus",
+        "Plus" => "plus",
+        "Period" => "period",
+        "Quote" => "apostrophe",
+        "Semicolon" => "semicolon",
+        "Slash" => "slash",
+        "Backspace" => "BackSpace",
+        "CapsLock" => "Caps_Lock",
+        "ContextMenu" => "Menu",
+        "Enter" => "Return",
+        "Space" => "space",
+        "PageDown" => "Next",
+        "PageUp" => "Prior",
+        "NumLock" => "Num_Lock",
+        "NumAdd" => "KP_Add",
+        "NumDivide" => "KP_Divide",
+        "NumSubstract" => "KP_Subtract",
+        "NumEnter" => "KP_Enter",
+        "NumComma" => "KP_Separator",
+        "PrintScreen" => "Print",
+        "ScrollLock" => "Scroll_Lock",
+        "VolumeMute" => "XF86AudioMute",
+        "VolumeDown" => "XF86AudioLowerVolume",
+        "VolumeUp" => "XF86AudioRaiseVolume",
+        "MediaNextTrack" => "XF86AudioNext",
+        "MediaPreviousTrack" => "XF86AudioPrev",
+        "MediaPlayPause" => "XF86AudioPlay",
+        _ => {
+            if key.len() == 1 {
+                trigger.push_str(&key.to_lowercase());
+            } else if let Some(digit) = key.strip_prefix("Num").filter(|n| n.len() == 1) {
+                trigger.push_str("KP_");
+                trigger.push_str(digit);
+            } else {
+                trigger.push_str(key);
+            }
+            return trigger;
+        }
+    };
+    trigger.push_str(keysym);
+    trigger
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{mpsc::{self, TryRecvError}, Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+    use gio::{glib::{self, ToVariant, Variant}, Cancellable, DBusCallFlags, DBusConnection, DBusNodeInfo};
+    use tauri::test::{mock_builder, mock_context, noop_assets};
+    use crate::{Error, Shortcut, ShortcutBackend, ShortcutState};
+    use crate::test_bus::PrivateBus;
+    use super::{
+        object_path, preferred_trigger, request, PortalShortcutBackend, Shared, GLOBAL_SHORTCUTS_INTERFACE, PORTAL_BUS_NAME,
+        PORTAL_OBJECT_PATH,
+    };
+
+    const PORTAL_XML: &str = r#"
+<node>
+  <interface name="org.freedesktop.portal.GlobalShortcuts">
+    <method name="CreateSession">
+      <arg name="options" type="a{sv}" direction="in"/>
+      <arg name="handle" type="o" direction="out"/>
+    </method>
+    <method name="BindShortcuts">
+      <arg name="session_handle" type="o" direction="in"/>
+      <arg name="shortcuts" type="a(sa{sv})" direction="in"/>
+      <arg name="parent_window" type="s" direction="in"/>
+      <arg name="options" type="a{sv}" direction="in"/>
+      <arg name="request_handle" type="o" direction="out"/>
+    </method>
+    <method name="ListShortcuts">
+      <arg name="session_handle" type="o" direction="in"/>
+      <arg name="options" type="a{sv}" direction="in"/>
+      <arg name="request_handle" type="o" direction="out"/>
+    </method>
+    <property name="version" type="u" access="read"/>
+  </interface>
+</node>
+"#;
+
+    type Shortcuts = Vec<(String, HashMap<String, Variant>)>;
+
+    /// Session and shortcuts of the last client of the mock portal.
+    #[derive(Default)]
+    struct Bound {
+        session: String,
+        shortcuts: Shortcuts,
+        /// Number of sessions created
+        sessions: usize,
+        /// Ids of the shortcuts of each call to `BindShortcuts`
+        binds: Vec<Vec<String>>,
+        /// Leaves requests without a response, like a user never confirming them
+        silent: bool,
+    }
+
+    /// Answers requests like a desktop accepting the preferred trigger of every shortcut, and
+    /// emits the signals sent to the returned channel for the bound shortcuts.
+    fn spawn_portal(connection: DBusConnection) -> (mpsc::Sender<&'static str>, Arc<Mutex<Bound>>) {
+        let (signal_tx, signal_rx) = mpsc::channel::<&'static str>();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let bound = Arc::new(Mutex::new(Bound::default()));
+        let portal_bound = bound.clone();
+        thread::spawn(move || {
+            let context = glib::MainContext::new();
+            context
+                .with_thread_default(|| {
+                    let bound = portal_bound;
+                    let interface_info = DBusNodeInfo::for_xml(PORTAL_XML)
+                        .unwrap()
+                        .lookup_interface(GLOBAL_SHORTCUTS_INTERFACE)
+                        .unwrap();
+                    let bound_ = bound.clone();
+                    connection
+                        .register_object(
+                            PORTAL_OBJECT_PATH,
+                            &interface_info,
+                            move |connection, sender, _, _, method, parameters, invocation| {
+                                let options_index = match method {
+                                    "CreateSession" => 0,
+                                    "BindShortcuts" => 3,
+                                    _ => 1,
+                                };
+                                let options: HashMap<String, Variant> = parameters.child_value(options_index).get().unwrap();
+                                let sender_path = sender.trim_start_matches(':').replace('.', "_");
+                                let token = options["handle_token"].str().unwrap();
+                                let request_path = format!("{}/request/{}/{}", PORTAL_OBJECT_PATH, sender_path, token);
+                                let mut bound = bound_.lock().unwrap();
+                                let mut results = HashMap::new();
+                                match method {
+                                    "CreateSession" => {
+                                        let session_token = options["session_handle_token"].str().unwrap();
+                                        bound.session = format!("{}/session/{}/{}", PORTAL_OBJECT_PATH, sender_path, session_token);
+                                        bound.shortcuts.clear();
+                                        bound.sessions += 1;
+                                        results.insert(String::from("session_handle"), bound.session.to_variant());
+                                    }
+                                    "BindShortcuts" => {
+                                        let shortcuts: Shortcuts = parameters.child_value(1).get().unwrap();
+                                        bound.binds.push(shortcuts.iter().map(|(id, _)| id.clone()).collect());
+                                        bound.shortcuts.extend(shortcuts.into_iter().map(|(id, mut properties)| {
+                                            let trigger = properties["preferred_trigger"].clone();
+                                            properties.insert(String::from("trigger_description"), trigger);
+                                            (id, properties)
+                                        }));
+                                        results.insert(String::from("shortcuts"), bound.shortcuts.to_variant());
+                                    }
+                                    _ => {
+                                        results.insert(String::from("shortcuts"), bound.shortcuts.to_variant());
+                                    }
+                                }
+                                invocation.return_value(Some(&Variant::tuple_from_iter([object_path(&request_path).unwrap()])));
+                                if bound.silent {
+                                    return;
+                                }
+                                connection
+                                    .emit_signal(
+                                        Some(sender),
+                                        &request_path,
+                                        "org.freedesktop.portal.Request",
+                                        "Response",
+                                        Some(&(0u32, results).to_variant()),
+                                    )
+                                    .unwrap();
+                            },
+                            |_, _, _, _, _| 1u32.to_variant(),
+                            |_, _, _, _, _, _| false,
+                        )
+                        .unwrap();
+                    connection
+                        .call_sync(
+                            Some("org.freedesktop.DBus"),
+                            "/org/freedesktop/DBus",
+                            "org.freedesktop.DBus",
+                            "RequestName",
+                            Some(&(PORTAL_BUS_NAME, 0u32).to_variant()),
+                            None,
+                            DBusCallFlags::NONE,
+                            -1,
+                            None::<&Cancellable>,
+                        )
+                        .unwrap();
+                    ready_tx.send(()).unwrap();
+                    loop {
+                        context.iteration(false);
+                        let signal = match signal_rx.try_recv() {
+                            Ok(signal) => signal,
+                            Err(TryRecvError::Empty) => {
+                                thread::sleep(Duration::from_millis(1));
+                                continue;
+                            }
+                            Err(TryRecvError::Disconnected) => break,
+                        };
+                        let bound = bound.lock().unwrap();
+                        for (id, _) in &bound.shortcuts {
+                            let parameters = Variant::tuple_from_iter([
+                                object_path(&bound.session).unwrap(),
+                                id.to_variant(),
+                                0u64.to_variant(),
+                                HashMap::<String, Variant>::new().to_variant(),
+                            ]);
+                            connection
+                                .emit_signal(None, PORTAL_OBJECT_PATH, GLOBAL_SHORTCUTS_INTERFACE, signal, Some(&parameters))
+                                .unwrap();
+                        }
+                    }
+                })
+                .unwrap();
+        });
+        ready_rx.recv().unwrap();
+        (signal_tx, bound)
+    }
+
+    #[test]
+    fn format_preferred_triggers() {
+        assert_eq!(preferred_trigger(&"Ctrl+Shift+J".parse().unwrap()), "CTRL+SHIFT+j");
+        assert_eq!(preferred_trigger(&"CmdOrCtrl+Space".parse().unwrap()), "CTRL+space");
+        assert_eq!(preferred_trigger(&"Super+Alt+F12".parse().unwrap()), "ALT+LOGO+F12");
+        assert_eq!(preferred_trigger(&"Alt+Num5".parse().unwrap()), "ALT+KP_5");
+    }
+
+    #[test]
+    fn bind_and_activate_shortcuts() {
+        let bus = PrivateBus::spawn();
+        let (signals, portal) = spawn_portal(bus.connect());
+        let app = mock_builder().build(mock_context(noop_assets())).unwrap();
+        let backend = PortalShortcutBackend::with_connection(app.handle(), bus.connect()).unwrap();
+        let shortcut: Shortcut = "Ctrl+Shift+K".parse().unwrap();
+        let (state_tx, state_rx) = mpsc::channel();
+        let state_tx = Mutex::new(state_tx);
+        backend
+            .register(&shortcut, Box::new(move |state| {
+                let _ = state_tx.lock().unwrap().send(state);
+            }))
+            .unwrap();
+        assert!(backend.is_registered(&shortcut).unwrap());
+
+        // shortcuts are bound in the background
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while backend.trigger_description(&shortcut).is_none() {
+            assert!(Instant::now() < deadline, "the shortcut was not bound");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(backend.trigger_description(&shortcut).as_deref(), Some("CTRL+SHIFT+k"));
+
+        signals.send("Activated").unwrap();
+        signals.send("Deactivated").unwrap();
+        assert_eq!(state_rx.recv_timeout(Duration::from_secs(5)).unwrap(), ShortcutState::Pressed);
+        assert_eq!(state_rx.recv_timeout(Duration::from_secs(5)).unwrap(), ShortcutState::Released);
+
+        backend.unregister(&shortcut).unwrap();
+        assert!(!backend.is_registered(&shortcut).unwrap());
+        assert_eq!(backend.trigger_description(&shortcut), None);
+
+        // later shortcuts are added to the same session, unregistered ones stay bound
+        let other: Shortcut = "Ctrl+Shift+L".parse().unwrap();
+        backend.register(&other, Box::new(|_| {})).unwrap();
+        backend.register(&shortcut, Box::new(|_| {})).unwrap();
+        while backend.trigger_description(&other).is_none() {
+            assert!(Instant::now() < deadline, "the shortcut was not bound");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(backend.trigger_description(&shortcut).as_deref(), Some("CTRL+SHIFT+k"));
+        let portal = portal.lock().unwrap();
+        assert_eq!(portal.sessions, 1);
+        assert_eq!(portal.binds, vec![vec![shortcut.to_string()], vec![other.to_string()]]);
+    }
+
+    #[test]
+    fn unanswered_requests_time_out() {
+        let bus = PrivateBus::spawn();
+        let (_signals, portal) = spawn_portal(bus.connect());
+        portal.lock().unwrap().silent = true;
+        let connection = bus.connect();
+        let context = glib::MainContext::new();
+        let result = context
+            .with_thread_default(|| {
+                let mut options = HashMap::new();
+                options.insert(String::from("session_handle_token"), "spotlight".to_variant());
+                request(&connection, &Shared::default(), "CreateSession", options, Duration::from_millis(200), |options| {
+                    Variant::tuple_from_iter([options])
+                })
+            })
+            .unwrap();
+        assert!(matches!(result, Err(Error::PortalRequestTimedOut { method }) if method == "CreateSession"));
+    }
+}
@@ -0,0 +1,166 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, sync::{Mutex, MutexGuard}};
+use tauri::{AppHandle, Runtime};
+use crate::Error;
+use crate::error::report_error;
+use crate::geometry::Rect;
+
+const GEOMETRY_FILE_NAME: &str = "spotlight-geometry.json";
+
+/// Frames of the windows relative to the top-left corner of each monitor they were moved to,
+/// keyed by window label, then by monitor, see `monitor_key`.
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+pub(crate) struct SavedGeometry(HashMap<String, HashMap<String, Rect>>);
+
+impl SavedGeometry {
+    /// Reads the geometry from `path`, a missing file yields no geometry.
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub(crate) fn get(&self, label: &str) -> Option<&HashMap<String, Rect>> {
+        self.0.get(label)
+    }
+
+    pub(crate) fn set(&mut self, label: &str, monitor: String, frame: Rect) {
+        self.0.entry(String::from(label)).or_default().insert(monitor, frame);
+    }
+}
+
+/// Identifies a monitor by its name, or by its frame for monitors without a name.
+pub(crate) fn monitor_key(name: Option<&str>, frame: Rect) -> String {
+    match name {
+        Some(name) => String::from(name),
+        None => format!("{}x{}+{}+{}", frame.width, frame.height, frame.x, frame.y),
+    }
+}
+
+/// Location of the geometry file in the app data directory.
+fn geometry_path<R: Runtime>(app_handle: &AppHandle<R>) -> Result<PathBuf, Error> {
+    app_handle
+        .path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(GEOMETRY_FILE_NAME))
+        .ok_or_else(|| Error::Other(String::from("failed to resolve app data dir")))
+}
+
+/// The geometry of the windows with `remember_geometry`, read on first use. Moves are
+/// recorded in memory and written to disk when a window is hidden.
+#[derive(Default, Debug)]
+pub(crate) struct GeometryStore(Mutex<Option<SavedGeometry>>);
+
+impl GeometryStore {
+    fn lock<R: Runtime>(&self, app_handle: &AppHandle<R>) -> Result<MutexGuard<'_, Option<SavedGeometry>>, Error> {
+        let mut guard = self
+            .0
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock saved geometry")))?;
+        if guard.is_none() {
+            // a broken file only loses the remembered geometry
+            let saved = SavedGeometry::load(&geometry_path(app_handle)?).unwrap_or_default();
+            *guard = Some(saved);
+        }
+        Ok(guard)
+    }
+
+    /// Returns the frames of window `label`, keyed by monitor.
+    pub(crate) fn frames<R: Runtime>(&self, app_handle: &AppHandle<R>, label: &str) -> Result<HashMap<String, Rect>, Error> {
+        Ok(self
+            .lock(app_handle)?
+            .as_ref()
+            .and_then(|saved| saved.get(label))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    pub(crate) fn record<R: Runtime>(&self, app_handle: &AppHandle<R>, label: &str, monitor: String, frame: Rect) -> Result<(), Error> {
+        if let Some(saved) = self.lock(app_handle)?.as_mut() {
+            saved.set(label, monitor, frame);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn save<R: Runtime>(&self, app_handle: &AppHandle<R>) -> Result<(), Error> {
+        match self.lock(app_handle)?.as_ref() {
+            Some(saved) => saved.save(&geometry_path(app_handle)?),
+            None => Ok(()),
+        }
+    }
+
+    /// Like `frames`, for a window about to be shown. A store that can not be read is reported
+    /// and only loses the remembered geometry.
+    pub(crate) fn frames_or_report<R: Runtime>(&self, app_handle: &AppHandle<R>, label: &str) -> HashMap<String, Rect> {
+        self.frames(app_handle, label).unwrap_or_else(|err| {
+            report_error(app_handle, err);
+            HashMap::new()
+        })
+    }
+
+    /// Keeps where a window about to be hidden was left: its last position and, if it remembers
+    /// its geometry, its frame, written to disk. Failures are reported rather than returned, so
+    /// that they never keep the window from hiding.
+    pub(crate) fn remember_or_report<R: Runtime>(
+        &self,
+        app_handle: &AppHandle<R>,
+        remember_geometry: bool,
+        save_last_position: impl FnOnce() -> Result<(), Error>,
+        record_geometry: impl FnOnce() -> Result<(), Error>,
+    ) {
+        let mut results = vec![save_last_position()];
+        if remember_geometry {
+            results.push(record_geometry().and_then(|_| self.save(app_handle)));
+        }
+        for err in results.into_iter().filter_map(Result::err) {
+            report_error(app_handle, err);
+        }
+    }
+
+    /// Makes every later access fail, like a store that can not be used.
+    #[cfg(test)]
+    pub(crate) fn poison(&self) {
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = self.0.lock();
+            panic!("poisoning the saved geometry");
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use super::{monitor_key, SavedGeometry};
+    use crate::geometry::Rect;
+
+    #[test]
+    fn save_and_load() {
+        let dir = env::temp_dir().join("spotlight-geometry-save");
+        let path = dir.join("geometry.json");
+        assert_eq!(SavedGeometry::load(&path).unwrap(), SavedGeometry::default());
+        let mut saved = SavedGeometry::default();
+        let frame = Rect { x: 100.0, y: 50.0, width: 800.0, height: 400.0 };
+        saved.set("main", String::from("DELL U2720Q"), frame);
+        saved.save(&path).unwrap();
+        let loaded = SavedGeometry::load(&path).unwrap();
+        assert_eq!(loaded.get("main").and_then(|frames| frames.get("DELL U2720Q")), Some(&frame));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn key_unnamed_monitors_by_frame() {
+        let frame = Rect { x: -1920.0, y: 0.0, width: 1920.0, height: 1080.0 };
+        assert_eq!(monitor_key(Some("Built-in Retina Display"), frame), "Built-in Retina Display");
+        assert_eq!(monitor_key(None, frame), "1920x1080+-1920+0");
+    }
+}
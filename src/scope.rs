@@ -0,0 +1,103 @@
+use crate::{Error, PluginConfig};
+
+/// Matches every window label or command.
+const WILDCARD: &str = "*";
+
+/// Allows the windows in `windows` to invoke `commands` on the spotlight windows in `targets`.
+///
+/// Each list may contain `"*"` to match anything.
+#[derive(serde::Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct CommandScope {
+    /// Labels of the calling windows
+    pub windows: Vec<String>,
+    /// Command names, e.g. `show` or `set_shortcut`
+    pub commands: Vec<String>,
+    /// Labels of the windows the commands act on. When omitted, a window may only target itself.
+    pub targets: Option<Vec<String>>,
+}
+
+fn matches(patterns: &[String], value: &str) -> bool {
+    patterns.iter().any(|pattern| pattern == WILDCARD || pattern == value)
+}
+
+impl CommandScope {
+    fn allows(&self, command: &str, caller: &str, target: &str) -> bool {
+        let target_allowed = match &self.targets {
+            Some(targets) => matches(targets, target),
+            None => caller == target,
+        };
+        matches(&self.windows, caller) && matches(&self.commands, command) && target_allowed
+    }
+}
+
+impl PluginConfig {
+    /// Checks that window `caller` may invoke `command` on window `target`.
+    ///
+    /// Every call is allowed when no `command_scopes` are configured.
+    pub(crate) fn check_command_scope(&self, command: &str, caller: &str, target: &str) -> Result<(), Error> {
+        let scopes = match &self.command_scopes {
+            Some(scopes) => scopes,
+            None => return Ok(()),
+        };
+        if scopes.iter().any(|scope| scope.allows(command, caller, target)) {
+            Ok(())
+        } else {
+            Err(Error::CommandNotAllowed {
+                command: String::from(command),
+                caller: String::from(caller),
+                target: String::from(target),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CommandScope;
+    use crate::{Error, PluginConfig};
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| String::from(*value)).collect()
+    }
+
+    #[test]
+    fn allow_everything_without_scopes() {
+        let config = PluginConfig::default();
+        assert!(config.check_command_scope("show", "settings", "main").is_ok());
+    }
+
+    #[test]
+    fn restrict_callers_commands_and_targets() {
+        let config = PluginConfig {
+            command_scopes: Some(vec![
+                CommandScope {
+                    windows: strings(&["settings"]),
+                    commands: strings(&["show", "toggle"]),
+                    targets: Some(strings(&["main"])),
+                },
+                CommandScope {
+                    windows: strings(&["*"]),
+                    commands: strings(&["hide"]),
+                    targets: None,
+                },
+            ]),
+            ..Default::default()
+        };
+        assert!(config.check_command_scope("show", "settings", "main").is_ok());
+        assert!(config.check_command_scope("hide", "main", "main").is_ok());
+        assert!(config.check_command_scope("hide", "settings", "main").is_err());
+        assert!(config.check_command_scope("show", "other", "main").is_err());
+        let err = config.check_command_scope("set_shortcut", "settings", "main").unwrap_err();
+        assert!(matches!(err, Error::CommandNotAllowed { command, .. } if command == "set_shortcut"));
+    }
+
+    #[test]
+    fn deserialize_scope() {
+        let scope: CommandScope = serde_json::from_value(serde_json::json!({
+            "windows": ["settings"],
+            "commands": ["*"],
+        })).unwrap();
+        assert_eq!(scope.targets, None);
+        assert!(scope.allows("register", "settings", "settings"));
+    }
+}
@@ -0,0 +1,396 @@
+use std::{fmt, str::FromStr};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Modifier keys of a [`Shortcut`], in the order they are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Modifier {
+    /// `Command` on macOS and `Control` on other platforms
+    CommandOrControl,
+    Control,
+    Alt,
+    Shift,
+    Super,
+}
+
+impl Modifier {
+    fn parse(token: &str) -> Option<Self> {
+        match token.to_uppercase().as_str() {
+            "COMMANDORCONTROL" | "COMMANDORCTRL" | "CMDORCTRL" | "CMDORCONTROL" => Some(Modifier::CommandOrControl),
+            "CONTROL" | "CTRL" => Some(Modifier::Control),
+            "OPTION" | "ALT" => Some(Modifier::Alt),
+            "SHIFT" => Some(Modifier::Shift),
+            "COMMAND" | "CMD" | "SUPER" => Some(Modifier::Super),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Modifier::CommandOrControl => "CommandOrControl",
+            Modifier::Control => "Control",
+            Modifier::Alt => "Alt",
+            Modifier::Shift => "Shift",
+            Modifier::Super => "Super",
+        }
+    }
+}
+
+impl fmt::Display for Modifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ShortcutParseError {
+    #[error("empty token in shortcut \"{shortcut}\"")]
+    EmptyToken { shortcut: String },
+    #[error("invalid token \"{token}\" in shortcut \"{shortcut}\"")]
+    InvalidToken { shortcut: String, token: String },
+    #[error("unexpected token \"{token}\" after the key in shortcut \"{shortcut}\"")]
+    UnexpectedToken { shortcut: String, token: String },
+    #[error("missing key in shortcut \"{shortcut}\"")]
+    MissingKey { shortcut: String },
+}
+
+impl ShortcutParseError {
+    pub fn shortcut(&self) -> &str {
+        match self {
+            ShortcutParseError::EmptyToken { shortcut }
+            | ShortcutParseError::InvalidToken { shortcut, .. }
+            | ShortcutParseError::UnexpectedToken { shortcut, .. }
+            | ShortcutParseError::MissingKey { shortcut } => shortcut,
+        }
+    }
+}
+
+/// A keyboard accelerator such as `CmdOrCtrl+Shift+J`, made of modifiers and one key.
+///
+/// Aliases are normalized while parsing, so `Ctrl+Option+K` and `control+alt+k`
+/// are the same shortcut and both display as `Control+Alt+K`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    modifiers: Vec<Modifier>,
+    key: String,
+}
+
+impl Shortcut {
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Modifiers with `CommandOrControl` replaced by the key it stands for on this platform.
+    pub fn resolved_modifiers(&self) -> Vec<Modifier> {
+        let mut modifiers: Vec<Modifier> = self
+            .modifiers
+            .iter()
+            .map(|modifier| match modifier {
+                #[cfg(target_os = "macos")]
+                Modifier::CommandOrControl => Modifier::Super,
+                #[cfg(not(target_os = "macos"))]
+                Modifier::CommandOrControl => Modifier::Control,
+                modifier => *modifier,
+            })
+            .collect();
+        modifiers.sort();
+        modifiers.dedup();
+        modifiers
+    }
+
+    /// Whether both shortcuts are triggered by the same key combination on this platform.
+    pub fn conflicts_with(&self, other: &Shortcut) -> bool {
+        self.key == other.key && self.resolved_modifiers() == other.resolved_modifiers()
+    }
+}
+
+/// A shortcut as seen by a DOM `keydown` event, used to handle the close shortcut
+/// inside the webview.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyBinding {
+    /// `KeyboardEvent.code` of the key
+    pub code: String,
+    pub ctrl_key: bool,
+    pub alt_key: bool,
+    pub shift_key: bool,
+    pub meta_key: bool,
+}
+
+impl From<&Shortcut> for KeyBinding {
+    fn from(shortcut: &Shortcut) -> Self {
+        let modifiers = shortcut.resolved_modifiers();
+        Self {
+            code: dom_code(&shortcut.key),
+            ctrl_key: modifiers.contains(&Modifier::Control),
+            alt_key: modifiers.contains(&Modifier::Alt),
+            shift_key: modifiers.contains(&Modifier::Shift),
+            meta_key: modifiers.contains(&Modifier::Super),
+        }
+    }
+}
+
+/// Maps a canonical key name to the matching `KeyboardEvent.code` value.
+fn dom_code(key: &str) -> String {
+    let code = match key {
+        "\\" => "Backslash",
+        "=" | "Plus" => "Equal",
+        "-" => "Minus",
+        "Up" => "ArrowUp",
+        "Down" => "ArrowDown",
+        "Left" => "ArrowLeft",
+        "Right" => "ArrowRight",
+        "NumAdd" => "NumpadAdd",
+        "NumBackspace" => "NumpadBackspace",
+        "NumClear" => "NumpadClear",
+        "NumComma" => "NumpadComma",
+        "NumDivide" => "NumpadDivide",
+        "NumSubstract" => "NumpadSubtract",
+        "NumEnter" => "NumpadEnter",
+        "VolumeMute" => "AudioVolumeMute",
+        "VolumeDown" => "AudioVolumeDown",
+        "VolumeUp" => "AudioVolumeUp",
+        _ => {
+            return if key.len() == 1 && key.chars().all(|c| c.is_ascii_alphabetic()) {
+                format!("Key{}", key)
+            } else if key.len() == 1 && key.chars().all(|c| c.is_ascii_digit()) {
+                format!("Digit{}", key)
+            } else if let Some(digit) = key.strip_prefix("Num").filter(|n| n.len() == 1) {
+                format!("Numpad{}", digit)
+            } else {
+                String::from(key)
+            };
+        }
+    };
+    String::from(code)
+}
+
+/// Returns the canonical name of a key, following the names accepted by Tauri.
+fn parse_key(token: &str) -> Option<String> {
+    let upper = token.to_uppercase();
+    let key = match upper.as_str() {
+        "`" | "BACKQUOTE" => "Backquote",
+        "BACKSLASH" => "Backslash",
+        "\\" => "\\",
+        "[" | "BRACKETLEFT" => "BracketLeft",
+        "]" | "BRACKETRIGHT" => "BracketRight",
+        "," | "COMMA" => "Comma",
+        "=" => "=",
+        "-" => "-",
+        "PLUS" => "Plus",
+        "." | "PERIOD" => "Period",
+        "'" | "QUOTE" => "Quote",
+        ";" | "SEMICOLON" => "Semicolon",
+        "/" | "SLASH" => "Slash",
+        "BACKSPACE" => "Backspace",
+        "CAPSLOCK" => "CapsLock",
+        "CONTEXTMENU" => "ContextMenu",
+        "ENTER" => "Enter",
+        "SPACE" => "Space",
+        "TAB" => "Tab",
+        "CONVERT" => "Convert",
+        "INSERT" => "Insert",
+        "DELETE" => "Delete",
+        "END" => "End",
+        "HELP" => "Help",
+        "HOME" => "Home",
+        "PAGEDOWN" => "PageDown",
+        "PAGEUP" => "PageUp",
+        "DOWN" | "ARROWDOWN" => "Down",
+        "UP" | "ARROWUP" => "Up",
+        "LEFT" | "ARROWLEFT" => "Left",
+        "RIGHT" | "ARROWRIGHT" => "Right",
+        "NUMLOCK" => "NumLock",
+        "NUMADD" | "NUMPADADD" => "NumAdd",
+        "NUMBACKSPACE" | "NUMPADBACKSPACE" => "NumBackspace",
+        "NUMCLEAR" | "NUMPADCLEAR" => "NumClear",
+        "NUMCOMMA" | "NUMPADCOMMA" => "NumComma",
+        "NUMDIVIDE" | "NUMPADDIVIDE" => "NumDivide",
+        "NUMSUBSTRACT" | "NUMPADSUBSTRACT" => "NumSubstract",
+        "NUMENTER" | "NUMPADENTER" => "NumEnter",
+        "ESC" | "ESCAPE" => "Escape",
+        "FN" => "Fn",
+        "FNLOCK" => "FnLock",
+        "PRINTSCREEN" => "PrintScreen",
+        "SCROLLLOCK" => "ScrollLock",
+        "PAUSE" => "Pause",
+        "VOLUMEMUTE" => "VolumeMute",
+        "VOLUMEDOWN" => "VolumeDown",
+        "VOLUMEUP" => "VolumeUp",
+        "MEDIANEXTTRACK" => "MediaNextTrack",
+        "MEDIAPREVIOUSTRACK" => "MediaPreviousTrack",
+        "MEDIAPLAYPAUSE" => "MediaPlayPause",
+        "LAUNCHMAIL" => "LaunchMail",
+        "SUSPEND" => "Suspend",
+        _ => {
+            let is_letter_or_digit = upper.len() == 1 && upper.chars().all(|c| c.is_ascii_alphanumeric());
+            let is_numpad = ["NUM", "NUMPAD"].iter().any(|prefix| {
+                upper.strip_prefix(prefix).map_or(false, |n| n.len() == 1 && n.chars().all(|c| c.is_ascii_digit()))
+            });
+            let is_function_key = upper
+                .strip_prefix('F')
+                .and_then(|n| n.parse::<u8>().ok())
+                .map_or(false, |n| (1..=35).contains(&n));
+            return if is_letter_or_digit || is_function_key {
+                Some(upper)
+            } else if is_numpad {
+                Some(format!("Num{}", &upper[upper.len() - 1..]))
+            } else {
+                None
+            };
+        }
+    };
+    Some(String::from(key))
+}
+
+impl FromStr for Shortcut {
+    type Err = ShortcutParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = vec![];
+        let mut key: Option<String> = None;
+        for raw in s.split('+') {
+            let token = raw.trim();
+            if token.is_empty() {
+                return Err(ShortcutParseError::EmptyToken { shortcut: String::from(s) });
+            }
+            if key.is_some() {
+                return Err(ShortcutParseError::UnexpectedToken {
+                    shortcut: String::from(s),
+                    token: String::from(token),
+                });
+            }
+            if let Some(modifier) = Modifier::parse(token) {
+                if !modifiers.contains(&modifier) {
+                    modifiers.push(modifier);
+                }
+            } else if let Some(k) = parse_key(token) {
+                key = Some(k);
+            } else {
+                return Err(ShortcutParseError::InvalidToken {
+                    shortcut: String::from(s),
+                    token: String::from(token),
+                });
+            }
+        }
+        let key = key.ok_or_else(|| ShortcutParseError::MissingKey { shortcut: String::from(s) })?;
+        modifiers.sort();
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier)?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl Serialize for Shortcut {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Shortcut {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyBinding, Modifier, Shortcut, ShortcutParseError};
+
+    #[test]
+    fn parse_and_normalize_aliases() {
+        let shortcut: Shortcut = "shift+CmdOrCtrl+j".parse().unwrap();
+        assert_eq!(shortcut.modifiers(), &[Modifier::CommandOrControl, Modifier::Shift]);
+        assert_eq!(shortcut.key(), "J");
+        assert_eq!(shortcut.to_string(), "CommandOrControl+Shift+J");
+
+        let a: Shortcut = "Ctrl+Option+K".parse().unwrap();
+        let b: Shortcut = "control + alt + k".parse().unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.to_string(), "Control+Alt+K");
+    }
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!("Esc".parse::<Shortcut>().unwrap().to_string(), "Escape");
+        assert_eq!("Cmd+ArrowUp".parse::<Shortcut>().unwrap().to_string(), "Super+Up");
+        assert_eq!("Alt+f12".parse::<Shortcut>().unwrap().to_string(), "Alt+F12");
+        assert_eq!("Ctrl+Numpad5".parse::<Shortcut>().unwrap().to_string(), "Control+Num5");
+    }
+
+    #[test]
+    fn report_invalid_token() {
+        assert_eq!("Ctrl+Shfit+J".parse::<Shortcut>(), Err(ShortcutParseError::InvalidToken {
+            shortcut: String::from("Ctrl+Shfit+J"),
+            token: String::from("Shfit"),
+        }));
+        assert_eq!("Ctrl+F36".parse::<Shortcut>(), Err(ShortcutParseError::InvalidToken {
+            shortcut: String::from("Ctrl+F36"),
+            token: String::from("F36"),
+        }));
+    }
+
+    #[test]
+    fn report_malformed_shortcut() {
+        assert_eq!("Ctrl++J".parse::<Shortcut>(), Err(ShortcutParseError::EmptyToken {
+            shortcut: String::from("Ctrl++J"),
+        }));
+        assert_eq!("Ctrl+J+Shift".parse::<Shortcut>(), Err(ShortcutParseError::UnexpectedToken {
+            shortcut: String::from("Ctrl+J+Shift"),
+            token: String::from("Shift"),
+        }));
+        assert_eq!("Ctrl+Shift".parse::<Shortcut>(), Err(ShortcutParseError::MissingKey {
+            shortcut: String::from("Ctrl+Shift"),
+        }));
+    }
+
+    #[test]
+    fn detect_overlapping_shortcuts() {
+        let a: Shortcut = "CmdOrCtrl+J".parse().unwrap();
+        #[cfg(target_os = "macos")]
+        let b: Shortcut = "Cmd+J".parse().unwrap();
+        #[cfg(not(target_os = "macos"))]
+        let b: Shortcut = "Ctrl+J".parse().unwrap();
+        assert_ne!(a, b);
+        assert!(a.conflicts_with(&b));
+        assert!(!a.conflicts_with(&"CmdOrCtrl+Shift+J".parse().unwrap()));
+    }
+
+    #[test]
+    fn key_binding_for_webview() {
+        let binding = KeyBinding::from(&"Esc".parse::<Shortcut>().unwrap());
+        assert_eq!(binding, KeyBinding {
+            code: String::from("Escape"),
+            ctrl_key: false,
+            alt_key: false,
+            shift_key: false,
+            meta_key: false,
+        });
+        let binding = KeyBinding::from(&"CmdOrCtrl+Shift+K".parse::<Shortcut>().unwrap());
+        assert_eq!(binding.code, "KeyK");
+        assert!(binding.shift_key);
+        assert_eq!(binding.meta_key, cfg!(target_os = "macos"));
+        assert_eq!(binding.ctrl_key, !cfg!(target_os = "macos"));
+        assert_eq!(KeyBinding::from(&"Alt+Up".parse::<Shortcut>().unwrap()).code, "ArrowUp");
+        assert_eq!(KeyBinding::from(&"Num7".parse::<Shortcut>().unwrap()).code, "Numpad7");
+    }
+
+    #[test]
+    fn deserialize_from_config() {
+        let shortcut: Shortcut = serde_json::from_str("\"CmdOrControl+Space\"").unwrap();
+        assert_eq!(shortcut.to_string(), "CommandOrControl+Space");
+        let err = serde_json::from_str::<Shortcut>("\"Ctrl+Foo\"").unwrap_err();
+        assert_eq!(err.to_string(), "invalid token \"Foo\" in shortcut \"Ctrl+Foo\"");
+    }
+}
@@ -0,0 +1,168 @@
+use std::{collections::HashMap, fmt, sync::{Arc, Mutex, MutexGuard, RwLock}};
+use tauri::{AppHandle, GlobalShortcutManager, Runtime};
+use crate::{Error, Shortcut};
+
+/// Whether a shortcut was pressed or released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutState {
+    Pressed,
+    /// Only reported by backends that see key releases
+    Released,
+}
+
+pub type ShortcutHandler = Box<dyn Fn(ShortcutState) + Send + Sync>;
+
+/// A handler that can be called without holding the lock of the shortcuts it is stored with.
+pub(crate) type SharedShortcutHandler = Arc<dyn Fn(ShortcutState) + Send + Sync>;
+
+/// A source of global shortcuts for the spotlight windows.
+pub trait ShortcutBackend: Send + Sync {
+    /// Starts calling `handler` whenever `shortcut` is pressed or released.
+    fn register(&self, shortcut: &Shortcut, handler: ShortcutHandler) -> Result<(), Error>;
+
+    fn unregister(&self, shortcut: &Shortcut) -> Result<(), Error>;
+
+    fn is_registered(&self, shortcut: &Shortcut) -> Result<bool, Error>;
+
+    /// How the user triggers `shortcut`, for backends that let the user pick the keys,
+    /// e.g. `Ctrl+Alt+Space`.
+    fn trigger_description(&self, _shortcut: &Shortcut) -> Option<String> {
+        None
+    }
+}
+
+/// The global shortcut manager of Tauri, which only reports key presses and can not grab
+/// keys under Wayland.
+pub struct TauriShortcutBackend<R: Runtime> {
+    app_handle: AppHandle<R>,
+}
+
+impl<R: Runtime> TauriShortcutBackend<R> {
+    pub fn new(app_handle: AppHandle<R>) -> Self {
+        Self { app_handle }
+    }
+}
+
+impl<R: Runtime> ShortcutBackend for TauriShortcutBackend<R> {
+    fn register(&self, shortcut: &Shortcut, handler: ShortcutHandler) -> Result<(), Error> {
+        let shortcut = shortcut.to_string();
+        self.app_handle
+            .global_shortcut_manager()
+            .register(&shortcut, move || handler(ShortcutState::Pressed))
+            .map_err(|_| Error::FailedToRegisterShortcut { shortcut })
+    }
+
+    fn unregister(&self, shortcut: &Shortcut) -> Result<(), Error> {
+        let shortcut = shortcut.to_string();
+        self.app_handle
+            .global_shortcut_manager()
+            .unregister(&shortcut)
+            .map_err(|_| Error::FailedToUnregisterShortcut { shortcut })
+    }
+
+    fn is_registered(&self, shortcut: &Shortcut) -> Result<bool, Error> {
+        self.app_handle
+            .global_shortcut_manager()
+            .is_registered(&shortcut.to_string())
+            .map_err(|err| Error::Tauri(err.into()))
+    }
+}
+
+/// Keeps shortcuts in memory and calls their handlers from `press` and `release`, to simulate
+/// key presses in tests. Clones share the same shortcuts.
+#[derive(Clone, Default)]
+pub struct FakeShortcutBackend(Arc<Mutex<HashMap<Shortcut, SharedShortcutHandler>>>);
+
+impl FakeShortcutBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls the handler of `shortcut` as if it was pressed, returns whether it is registered.
+    pub fn press(&self, shortcut: &Shortcut) -> bool {
+        self.trigger(shortcut, ShortcutState::Pressed)
+    }
+
+    /// Calls the handler of `shortcut` as if it was released, returns whether it is registered.
+    pub fn release(&self, shortcut: &Shortcut) -> bool {
+        self.trigger(shortcut, ShortcutState::Released)
+    }
+
+    /// Returns the registered shortcuts, sorted by their display.
+    pub fn registered(&self) -> Vec<Shortcut> {
+        let mut shortcuts: Vec<Shortcut> = self.0.lock().map(|handlers| handlers.keys().cloned().collect()).unwrap_or_default();
+        shortcuts.sort_by_key(Shortcut::to_string);
+        shortcuts
+    }
+
+    fn trigger(&self, shortcut: &Shortcut, state: ShortcutState) -> bool {
+        // handlers may register or unregister shortcuts themselves
+        let handler = self.0.lock().ok().and_then(|handlers| handlers.get(shortcut).cloned());
+        match handler {
+            Some(handler) => {
+                handler(state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<Shortcut, SharedShortcutHandler>>, Error> {
+        self.0
+            .lock()
+            .map_err(|_| Error::Mutex(String::from("failed to lock fake shortcuts")))
+    }
+}
+
+impl ShortcutBackend for FakeShortcutBackend {
+    fn register(&self, shortcut: &Shortcut, handler: ShortcutHandler) -> Result<(), Error> {
+        let mut handlers = self.lock()?;
+        if handlers.contains_key(shortcut) {
+            return Err(Error::FailedToRegisterShortcut { shortcut: shortcut.to_string() });
+        }
+        handlers.insert(shortcut.clone(), Arc::from(handler));
+        Ok(())
+    }
+
+    fn unregister(&self, shortcut: &Shortcut) -> Result<(), Error> {
+        match self.lock()?.remove(shortcut) {
+            Some(_) => Ok(()),
+            None => Err(Error::FailedToUnregisterShortcut { shortcut: shortcut.to_string() }),
+        }
+    }
+
+    fn is_registered(&self, shortcut: &Shortcut) -> Result<bool, Error> {
+        Ok(self.lock()?.contains_key(shortcut))
+    }
+}
+
+impl fmt::Debug for FakeShortcutBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FakeShortcutBackend").field(&self.registered()).finish()
+    }
+}
+
+/// The backend the manager registers shortcuts with, Tauri's unless another one was set.
+#[derive(Default)]
+pub(crate) struct ShortcutBackendSlot(RwLock<Option<Arc<dyn ShortcutBackend>>>);
+
+impl ShortcutBackendSlot {
+    pub(crate) fn set(&self, backend: Arc<dyn ShortcutBackend>) {
+        if let Ok(mut guard) = self.0.write() {
+            guard.replace(backend);
+        }
+    }
+
+    pub(crate) fn get<R: Runtime>(&self, app_handle: &AppHandle<R>) -> Arc<dyn ShortcutBackend> {
+        match self.0.read().ok().and_then(|guard| guard.clone()) {
+            Some(backend) => backend,
+            None => Arc::new(TauriShortcutBackend::new(app_handle.clone())),
+        }
+    }
+}
+
+impl fmt::Debug for ShortcutBackendSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ShortcutBackendSlot")
+    }
+}
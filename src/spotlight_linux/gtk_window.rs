@@ -0,0 +1,202 @@
+use std::{cell::RefCell, collections::HashMap};
+use gtk::prelude::*;
+use tauri::{Monitor, Runtime, Window};
+use crate::{Error, Reason, WindowConfig};
+#[cfg(feature = "layer-shell")]
+use tauri::Manager;
+#[cfg(feature = "layer-shell")]
+use crate::{error::report_error, Position};
+use crate::geometry::{Point, Rect};
+#[cfg(feature = "layer-shell")]
+use super::layer_shell;
+use crate::manager::{monitor_rect, Platform};
+
+thread_local! {
+    /// Windows that were active when a spotlight window was shown, keyed by its label.
+    /// GDK objects never leave the main thread.
+    static PREVIOUS_WINDOWS: RefCell<HashMap<String, gdk::Window>> = RefCell::new(HashMap::new());
+}
+
+/// Runs `f` on the main thread and waits for its result, since GTK may only be used there
+/// and commands run elsewhere.
+fn on_main_thread<R: Runtime, T: Send + 'static, F: FnOnce() -> T + Send + 'static>(window: &Window<R>, f: F) -> Option<T> {
+    if gdk::glib::MainContext::default().is_owner() {
+        return Some(f());
+    }
+    let (tx, rx) = std::sync::mpsc::channel();
+    window.run_on_main_thread(move || {
+        let _ = tx.send(f());
+    }).ok()?;
+    rx.recv().ok()
+}
+
+/// Returns the GDK monitor matching `monitor`, whose position Tauri scales to physical pixels.
+fn gdk_monitor(monitor: &Monitor) -> Option<gdk::Monitor> {
+    let position = (monitor.position().x, monitor.position().y);
+    let display = gdk::Display::default()?;
+    (0..display.n_monitors()).filter_map(|index| display.monitor(index)).find(|gdk_monitor| {
+        let geometry = gdk_monitor.geometry();
+        let scale_factor = gdk_monitor.scale_factor();
+        (geometry.x() * scale_factor, geometry.y() * scale_factor) == position
+    })
+}
+
+/// GTK specifics of spotlight windows on Linux.
+pub(crate) struct LinuxPlatform;
+
+impl Platform for LinuxPlatform {
+    /// Makes the window behave like a panel: a utility window kept above the others on every
+    /// workspace, and left out of the taskbar and the pager.
+    ///
+    /// With the `layer-shell` feature on a Wayland compositor supporting it, the window becomes
+    /// an overlay layer surface instead, if it was created with `"visible": false`.
+    ///
+    /// The hints are applied when the window is mapped, i.e. the next time it is shown.
+    fn init_window<R: Runtime>(window: &Window<R>, _window_config: &WindowConfig) -> Result<(), Error> {
+        // GTK is not running in tests
+        if !gtk::is_initialized() {
+            return Ok(());
+        }
+        let window_ = window.clone();
+        on_main_thread(window, move || {
+            let gtk_window = window_.gtk_window()?;
+            #[cfg(feature = "layer-shell")]
+            match layer_shell::init(&gtk_window, window_.label()) {
+                Ok(true) => return Ok(()),
+                Ok(false) => {}
+                // keep a realized window as a regular window rather than failing registration
+                Err(err) => report_error(&window_.app_handle(), err),
+            }
+            gtk_window.set_type_hint(gdk::WindowTypeHint::Utility);
+            gtk_window.set_keep_above(true);
+            gtk_window.stick();
+            gtk_window.set_skip_taskbar_hint(true);
+            gtk_window.set_skip_pager_hint(true);
+            Ok(())
+        })
+        .unwrap_or(Ok(()))
+    }
+
+    /// Remembers the active window, to give it the focus back once `window` is hidden.
+    ///
+    /// Only works on X11, Wayland does not expose other windows.
+    fn will_show<R: Runtime>(window: &Window<R>) {
+        if !gtk::is_initialized() {
+            return;
+        }
+        let label = window.label().to_string();
+        on_main_thread(window, move || {
+            // deprecated without replacement, foreign windows are out of reach otherwise
+            #[allow(deprecated)]
+            let active_window = gdk::Screen::default().and_then(|screen| screen.active_window());
+            PREVIOUS_WINDOWS.with(|previous| {
+                let mut previous = previous.borrow_mut();
+                match active_window {
+                    Some(active_window) => previous.insert(label, active_window),
+                    None => previous.remove(&label),
+                };
+            });
+        });
+    }
+
+    /// Gives the focus back to the window that was active when `window` was shown, unless it
+    /// was hidden on blur, the user having already moved on to another window.
+    fn did_hide<R: Runtime>(window: &Window<R>, reason: Reason) {
+        if !gtk::is_initialized() || reason == Reason::Blur {
+            return;
+        }
+        let label = window.label().to_string();
+        on_main_thread(window, move || {
+            if let Some(previous) = PREVIOUS_WINDOWS.with(|previous| previous.borrow_mut().remove(&label)) {
+                previous.focus(gtk::current_event_time());
+            }
+        });
+    }
+
+    /// Places the window at `origin` within `area` on `monitor` if it is a layer surface, which
+    /// the compositor positions from anchors and margins. Returns `false` for other windows.
+    #[cfg(feature = "layer-shell")]
+    fn place_window<R: Runtime>(
+        window: &Window<R>,
+        monitor: &Monitor,
+        position: Option<&Position>,
+        area: Rect,
+        origin: Point,
+    ) -> bool {
+        if !gtk::is_initialized() {
+            return false;
+        }
+        let window_ = window.clone();
+        let monitor = monitor.clone();
+        let position = position.cloned();
+        on_main_thread(window, move || {
+            let gtk_window = match window_.gtk_window() {
+                Ok(gtk_window) => gtk_window,
+                Err(_) => return false,
+            };
+            if !layer_shell::is_layer_surface(&gtk_window) {
+                return false;
+            }
+            // margins are in logical pixels
+            let scale_factor = monitor.scale_factor();
+            let scale = |rect: Rect| Rect {
+                x: rect.x / scale_factor,
+                y: rect.y / scale_factor,
+                width: rect.width / scale_factor,
+                height: rect.height / scale_factor,
+            };
+            let area = scale(area);
+            let origin = Point { x: origin.x / scale_factor, y: origin.y / scale_factor };
+            let margins = layer_shell::margins(position.as_ref(), area, origin);
+            layer_shell::place(&gtk_window, gdk_monitor(&monitor).as_ref(), margins);
+            true
+        })
+        .unwrap_or(false)
+    }
+
+    /// Returns the part of the monitor not covered by panels and docks, in physical pixels.
+    ///
+    /// GDK reports both the monitor geometry and its work area in logical pixels.
+    fn work_area<R: Runtime>(window: &Window<R>, monitor: &Monitor) -> Rect {
+        let frame = monitor_rect(monitor);
+        let monitor_ = monitor.clone();
+        let work_area = on_main_thread(window, move || {
+            let gdk_monitor = gdk_monitor(&monitor_)?;
+            let geometry = gdk_monitor.geometry();
+            let workarea = gdk_monitor.workarea();
+            let scale_factor = gdk_monitor.scale_factor();
+            Some(Rect {
+                x: ((workarea.x() - geometry.x()) * scale_factor) as f64,
+                y: ((workarea.y() - geometry.y()) * scale_factor) as f64,
+                width: (workarea.width() * scale_factor) as f64,
+                height: (workarea.height() * scale_factor) as f64,
+            })
+        });
+        match work_area.flatten() {
+            Some(offset) => Rect { x: frame.x + offset.x, y: frame.y + offset.y, ..offset },
+            None => frame,
+        }
+    }
+
+    /// Returns the cursor position in physical pixels.
+    ///
+    /// GDK reports it in logical pixels, which are scaled by the factor of the monitor they fall on.
+    fn cursor_position<R: Runtime>(window: &Window<R>, monitors: &[Monitor]) -> Option<Point> {
+        fn pointer_position() -> Option<(i32, i32)> {
+            let (_, x, y) = gdk::Display::default()?.default_seat()?.pointer()?.position();
+            Some((x, y))
+        }
+        let (x, y) = on_main_thread(window, pointer_position)??;
+        monitors.iter().find_map(|monitor| {
+            let scale_factor = monitor.scale_factor();
+            let logical = monitor.position().to_logical::<f64>(scale_factor);
+            let size = monitor.size().to_logical::<f64>(scale_factor);
+            let area = Rect { x: logical.x, y: logical.y, width: size.width, height: size.height };
+            let point = Point { x: x as f64, y: y as f64 };
+            area.contains(point).then(|| Point {
+                x: monitor.position().x as f64 + (point.x - area.x) * scale_factor,
+                y: monitor.position().y as f64 + (point.y - area.y) * scale_factor,
+            })
+        })
+    }
+}
@@ -0,0 +1,161 @@
+use std::{ffi::CString, os::raw::{c_char, c_int}};
+use gtk::{glib::{ffi::gboolean, translate::ToGlibPtr}, prelude::*};
+use crate::{Anchor, Error, Position};
+use crate::geometry::{Point, Rect};
+
+/// Layer of the surface, above fullscreen windows
+const LAYER_OVERLAY: c_int = 3;
+/// The surface takes all keyboard input while it is mapped
+const KEYBOARD_MODE_EXCLUSIVE: c_int = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edge {
+    Left = 0,
+    Right = 1,
+    Top = 2,
+    Bottom = 3,
+}
+
+const EDGES: [Edge; 4] = [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom];
+
+// linked by the build script
+extern "C" {
+    fn gtk_layer_is_supported() -> gboolean;
+    fn gtk_layer_init_for_window(window: *mut gtk::ffi::GtkWindow);
+    fn gtk_layer_is_layer_window(window: *mut gtk::ffi::GtkWindow) -> gboolean;
+    fn gtk_layer_set_namespace(window: *mut gtk::ffi::GtkWindow, name_space: *const c_char);
+    fn gtk_layer_set_layer(window: *mut gtk::ffi::GtkWindow, layer: c_int);
+    fn gtk_layer_set_keyboard_mode(window: *mut gtk::ffi::GtkWindow, mode: c_int);
+    fn gtk_layer_set_monitor(window: *mut gtk::ffi::GtkWindow, monitor: *mut gdk::ffi::GdkMonitor);
+    fn gtk_layer_set_anchor(window: *mut gtk::ffi::GtkWindow, edge: c_int, anchor_to_edge: gboolean);
+    fn gtk_layer_set_margin(window: *mut gtk::ffi::GtkWindow, edge: c_int, margin_size: c_int);
+}
+
+/// Turns the window into an overlay layer surface with exclusive keyboard input, returning
+/// whether the compositor supports the layer-shell protocol.
+///
+/// A layer surface can only be set up before the window is realized, so windows must be
+/// created with `"visible": false`. Realized windows are left alone, as tearing down the
+/// surface of a webview window breaks its WebKit rendering.
+pub(crate) fn init(window: &impl IsA<gtk::Window>, namespace: &str) -> Result<bool, Error> {
+    if unsafe { gtk_layer_is_supported() } == 0 {
+        return Ok(false);
+    }
+    if window.upcast_ref::<gtk::Window>().is_realized() {
+        return Err(Error::Other(format!(
+            "window {} is already realized, create it with \"visible\": false to make it a layer surface",
+            namespace
+        )));
+    }
+    let namespace = CString::new(namespace).map_err(|err| Error::Other(err.to_string()))?;
+    let raw: *mut gtk::ffi::GtkWindow = window.upcast_ref::<gtk::Window>().to_glib_none().0;
+    unsafe {
+        gtk_layer_init_for_window(raw);
+        gtk_layer_set_namespace(raw, namespace.as_ptr());
+        gtk_layer_set_layer(raw, LAYER_OVERLAY);
+        gtk_layer_set_keyboard_mode(raw, KEYBOARD_MODE_EXCLUSIVE);
+    }
+    Ok(true)
+}
+
+pub(crate) fn is_layer_surface(window: &impl IsA<gtk::Window>) -> bool {
+    let raw: *mut gtk::ffi::GtkWindow = window.upcast_ref::<gtk::Window>().to_glib_none().0;
+    unsafe { gtk_layer_is_layer_window(raw) != 0 }
+}
+
+/// Places the layer surface on `monitor` with the anchors and margins of `margins`.
+pub(crate) fn place(window: &impl IsA<gtk::Window>, monitor: Option<&gdk::Monitor>, margins: [Option<i32>; 4]) {
+    let raw: *mut gtk::ffi::GtkWindow = window.upcast_ref::<gtk::Window>().to_glib_none().0;
+    unsafe {
+        if let Some(monitor) = monitor {
+            gtk_layer_set_monitor(raw, monitor.to_glib_none().0);
+        }
+        for (edge, margin) in EDGES.iter().zip(margins) {
+            gtk_layer_set_anchor(raw, *edge as c_int, margin.is_some() as gboolean);
+            gtk_layer_set_margin(raw, *edge as c_int, margin.unwrap_or(0));
+        }
+    }
+}
+
+/// Returns the margin of each edge the surface is anchored to, in the order of `EDGES`.
+///
+/// `Position::Fixed` anchors the surface to the edges of its anchor, and its offsets become
+/// margins. A centered axis is left to the compositor unless it has an offset, then it is
+/// anchored at `origin` like the other positions. Other positions anchor the top-left corner of
+/// the surface at `origin`, both `origin` and `area` being in logical pixels. Edges of the work
+/// area are left to the compositor.
+pub(crate) fn margins(position: Option<&Position>, area: Rect, origin: Point) -> [Option<i32>; 4] {
+    let round = |margin: f64| Some(margin.round() as i32);
+    if let Some(Position::Fixed { anchor, offset_x, offset_y }) = position {
+        let (left, right) = match anchor {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => (round(*offset_x), None),
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => (None, round(-offset_x)),
+            Anchor::Top | Anchor::Center | Anchor::Bottom if *offset_x == 0.0 => (None, None),
+            Anchor::Top | Anchor::Center | Anchor::Bottom => (round(origin.x - area.x), None),
+        };
+        let (top, bottom) = match anchor {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => (round(*offset_y), None),
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => (None, round(-offset_y)),
+            Anchor::Left | Anchor::Center | Anchor::Right if *offset_y == 0.0 => (None, None),
+            Anchor::Left | Anchor::Center | Anchor::Right => (round(origin.y - area.y), None),
+        };
+        return [left, right, top, bottom];
+    }
+    [round(origin.x - area.x), None, round(origin.y - area.y), None]
+}
+
+#[cfg(test)]
+mod tests {
+    use gtk::prelude::*;
+    use super::{init, is_layer_surface, margins, place};
+    use crate::{Anchor, Position};
+    use crate::geometry::{Point, Rect};
+
+    const AREA: Rect = Rect { x: 0.0, y: 30.0, width: 1920.0, height: 1050.0 };
+
+    #[test]
+    fn fixed_positions_use_anchors() {
+        let position = Position::Fixed { anchor: Anchor::TopRight, offset_x: -20.0, offset_y: 40.0 };
+        assert_eq!(margins(Some(&position), AREA, Point::default()), [None, Some(20), Some(40), None]);
+        let position = Position::Fixed { anchor: Anchor::Bottom, offset_x: 0.0, offset_y: -10.0 };
+        assert_eq!(margins(Some(&position), AREA, Point::default()), [None, None, None, Some(10)]);
+    }
+
+    #[test]
+    fn centered_offsets_anchor_at_origin() {
+        let origin = Point { x: 710.0, y: 395.0 };
+        let position = Position::Fixed { anchor: Anchor::Center, offset_x: 50.0, offset_y: -20.0 };
+        assert_eq!(margins(Some(&position), AREA, origin), [Some(710), None, Some(365), None]);
+        let position = Position::Fixed { anchor: Anchor::Top, offset_x: 50.0, offset_y: 10.0 };
+        assert_eq!(margins(Some(&position), AREA, origin), [Some(710), None, Some(10), None]);
+        let position = Position::Fixed { anchor: Anchor::Right, offset_x: -20.0, offset_y: 30.0 };
+        assert_eq!(margins(Some(&position), AREA, origin), [None, Some(20), Some(365), None]);
+    }
+
+    #[test]
+    fn other_positions_anchor_top_left_corner() {
+        let origin = Point { x: 660.0, y: 380.0 };
+        assert_eq!(margins(Some(&Position::UpperThird), AREA, origin), [Some(660), None, Some(350), None]);
+        assert_eq!(margins(None, AREA, origin), [Some(660), None, Some(350), None]);
+    }
+
+    /// Needs a compositor with the layer-shell protocol, e.g. in a headless sway session:
+    /// `cargo test --features layer-shell -- --ignored layer_surface`
+    #[test]
+    #[ignore]
+    fn layer_surface_is_set_up_before_realization() {
+        gtk::init().expect("no display");
+        let window = gtk::Window::new(gtk::WindowType::Toplevel);
+        assert!(init(&window, "spotlight").expect("window is not realized"));
+        assert!(is_layer_surface(&window));
+        place(&window, None, margins(Some(&Position::UpperThird), AREA, Point { x: 660.0, y: 380.0 }));
+        window.show_all();
+        assert!(window.is_realized());
+        assert!(is_layer_surface(&window));
+
+        let realized = gtk::Window::new(gtk::WindowType::Toplevel);
+        realized.show_all();
+        assert!(init(&realized, "realized").is_err());
+        assert!(!is_layer_surface(&realized));
+    }
+}
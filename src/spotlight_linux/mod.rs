@@ -0,0 +1,5 @@
+mod gtk_window;
+#[cfg(feature = "layer-shell")]
+mod layer_shell;
+
+pub(crate) use gtk_window::LinuxPlatform as CurrentPlatform;
@@ -1,4 +1,4 @@
 mod panel;
 mod spotlight;
 
-pub use spotlight::SpotlightManager;
+pub(crate) use spotlight::MacosPlatform as CurrentPlatform;
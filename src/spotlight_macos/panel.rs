@@ -10,11 +10,12 @@ use objc::{
     class,
     declare::ClassDecl,
     msg_send,
-    runtime::{self, Class, Object, Protocol, Sel},
+    runtime::{self, Class, Object, Sel},
     sel, sel_impl, Message,
 };
 use objc_foundation::INSObject;
-use tauri::{Window, Wry};
+use tauri::{Runtime, Window};
+use crate::Error;
 
 extern "C" {
     pub fn object_setClass(obj: id, cls: id) -> id;
@@ -46,35 +47,15 @@ impl RawNSPanel {
             .unwrap_or_else(|| panic!("Unable to register {} class", CLS_NAME));
 
         unsafe {
-            cls.add_ivar::<BOOL>("_autoHide");
-
             cls.add_method(
                 sel!(canBecomeKeyWindow),
                 Self::can_become_key_window as extern "C" fn(&Object, Sel) -> BOOL,
             );
-
-            cls.add_method(
-                sel!(autoHide),
-                Self::_get_auto_hide as extern "C" fn(&mut Object, Sel) -> BOOL,
-            );
-
-            cls.add_method(
-                sel!(setAutoHide:),
-                Self::_set_auto_hide as extern "C" fn(&mut Object, Sel, BOOL),
-            );
         }
 
         cls.register()
     }
 
-    extern "C" fn _get_auto_hide(this: &mut Object, _: Sel) -> BOOL {
-        unsafe { *this.get_ivar("_autoHide") }
-    }
-
-    extern "C" fn _set_auto_hide(this: &mut Object, _: Sel, value: BOOL) {
-        unsafe { this.set_ivar("_autoHide", value) };
-    }
-
     /// Returns YES to ensure that RawNSPanel can become a key window
     extern "C" fn can_become_key_window(_: &Object, _: Sel) -> BOOL {
         YES
@@ -128,10 +109,6 @@ impl RawNSPanel {
         let _: () = unsafe { msg_send![self, setLevel: level] };
     }
 
-    pub(crate) fn set_auto_hide(&self, value: bool) {
-        let _: () = unsafe { msg_send![self, setAutoHide: value] };
-    }
-
     pub(crate) fn set_style_mask(&self, style_mask: i32) {
         let _: () = unsafe { msg_send![self, setStyleMask: style_mask] };
     }
@@ -140,20 +117,15 @@ impl RawNSPanel {
         let _: () = unsafe { msg_send![self, setCollectionBehavior: behaviour] };
     }
 
-    fn set_delegate(&self, delegate: Option<Id<RawNSPanelDelegate>>) {
-        if let Some(del) = delegate {
-            let _: () = unsafe { msg_send![self, setDelegate: del] };
-        } else {
-            let _: () = unsafe { msg_send![self, setDelegate: self] };
-        }
-    }
-
     /// Create an NSPanel from Tauri's NSWindow
+    ///
+    /// The window is owned by Tauri, so it is retained here to balance the release
+    /// when the returned `Id` is dropped.
     fn from(ns_window: id) -> Id<Self> {
         let ns_panel: id = unsafe { msg_send![Self::class(), class] };
         unsafe {
             object_setClass(ns_window, ns_panel);
-            Id::from_retained_ptr(ns_window as *mut Self)
+            Id::from_ptr(ns_window as *mut Self)
         }
     }
 }
@@ -164,92 +136,15 @@ impl INSObject for RawNSPanel {
     }
 }
 
-#[allow(dead_code)]
-const DELEGATE_CLS_NAME: &str = "RawNSPanelDelegate";
-
-#[allow(dead_code)]
-struct RawNSPanelDelegate {}
-
-impl RawNSPanelDelegate {
-    #[allow(dead_code)]
-    fn get_class() -> &'static Class {
-        Class::get(DELEGATE_CLS_NAME).unwrap_or_else(Self::define_class)
-    }
-
-    #[allow(dead_code)]
-    fn define_class() -> &'static Class {
-        let mut cls = ClassDecl::new(DELEGATE_CLS_NAME, class!(NSObject))
-            .unwrap_or_else(|| panic!("Unable to register {} class", DELEGATE_CLS_NAME));
-
-        cls.add_protocol(
-            Protocol::get("NSWindowDelegate").expect("Failed to get NSWindowDelegate protocol"),
-        );
-
-        unsafe {
-            cls.add_ivar::<id>("panel");
-
-            cls.add_method(
-                sel!(setPanel:),
-                Self::set_panel as extern "C" fn(&mut Object, Sel, id),
-            );
-
-            cls.add_method(
-                sel!(windowDidBecomeKey:),
-                Self::window_did_become_key as extern "C" fn(&Object, Sel, id),
-            );
-
-            cls.add_method(
-                sel!(windowDidResignKey:),
-                Self::window_did_resign_key as extern "C" fn(&Object, Sel, id),
-            );
-        }
-
-        cls.register()
-    }
-
-    extern "C" fn set_panel(this: &mut Object, _: Sel, panel: id) {
-        unsafe { this.set_ivar("panel", panel) };
-    }
-
-    extern "C" fn window_did_become_key(_: &Object, _: Sel, _: id) {}
-
-    /// Hide panel when it's no longer the key window and auto hide is enabled
-    extern "C" fn window_did_resign_key(this: &Object, _: Sel, _: id) {
-        let panel: id = unsafe { *this.get_ivar("panel") };
-        let auto_hide: BOOL = unsafe { msg_send![panel, autoHide] };
-
-        if auto_hide == YES {
-            let _: () = unsafe { msg_send![panel, orderOut: nil] };
-        }
-    }
-}
-
-unsafe impl Message for RawNSPanelDelegate {}
-
-impl INSObject for RawNSPanelDelegate {
-    fn class() -> &'static runtime::Class {
-        Self::get_class()
-    }
-}
-
-impl RawNSPanelDelegate {
-    pub fn set_panel_(&self, panel: ShareId<RawNSPanel>) {
-        let _: () = unsafe { msg_send![self, setPanel: panel] };
-    }
-}
-
-pub(crate) fn create_spotlight_panel(window: &Window<Wry>) -> ShareId<RawNSPanel> {
+pub(crate) fn create_spotlight_panel<R: Runtime>(window: &Window<R>) -> Result<(), Error> {
     // Convert NSWindow Object to NSPanel
-    let handle: id = window.ns_window().unwrap() as _;
+    let handle: id = window.ns_window().map_err(|_| Error::FailedToGetNSWindow)? as _;
     let panel = RawNSPanel::from(handle);
     let panel = panel.share();
 
     // Set panel above the main menu window level
     panel.set_level(NSMainMenuWindowLevel + 1);
 
-    // Set panel to auto hide when it resigns key
-    panel.set_auto_hide(true);
-
     // Ensure that the panel can display over the top of fullscreen apps
     panel.set_collection_behaviour(
         NSWindowCollectionBehavior::NSWindowCollectionBehaviorTransient
@@ -260,10 +155,8 @@ pub(crate) fn create_spotlight_panel(window: &Window<Wry>) -> ShareId<RawNSPanel
     // Ensures panel does not activate
     panel.set_style_mask(NSWindowStyleMaskNonActivatingPanel);
 
-    // Setup delegate for an NSPanel to listen for window resign key and hide the panel
-    let delegate = RawNSPanelDelegate::new();
-    delegate.set_panel_(panel.clone());
-    panel.set_delegate(Some(delegate));
+    // The delegate of tao is kept, it turns resigning key into `WindowEvent::Focused(false)`,
+    // which hides the panel through the manager like on the other platforms
 
     // On older macOS i.e on (12.3), hover detection is not working, see https://github.com/ahkohd/tauri-macos-spotlight-example/issues/14
     // To fix this, add a tracking view to the panel
@@ -286,5 +179,12 @@ pub(crate) fn create_spotlight_panel(window: &Window<Wry>) -> ShareId<RawNSPanel
     let () = unsafe { msg_send![view, setAutoresizingMask: auto_resizing_mask] };
     let () = unsafe { msg_send![view, addTrackingArea: track_view] };
 
-    panel
+    Ok(())
+}
+
+/// Returns the panel `create_spotlight_panel` turned the window into.
+pub(crate) fn spotlight_panel<R: Runtime>(window: &Window<R>) -> Result<ShareId<RawNSPanel>, Error> {
+    let handle: id = window.ns_window().map_err(|_| Error::FailedToGetNSWindow)? as _;
+    // retained like in `RawNSPanel::from`
+    Ok(unsafe { Id::from_ptr(handle as *mut RawNSPanel) }.share())
 }
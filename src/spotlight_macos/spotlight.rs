@@ -1,87 +1,144 @@
-use std::{collections::HashMap, sync::{Mutex, RwLock}};
+use std::collections::HashMap;
 use cocoa::{
-    appkit::{CGFloat, NSWindow},
-    base::{id, nil, BOOL, NO, YES},
-    foundation::{NSPoint, NSRect},
+    appkit::NSWindow,
+    base::{id, nil, YES},
+    foundation::{NSPoint, NSRect, NSSize},
 };
-use objc_id::ShareId;
 use objc::{class, msg_send, sel, sel_impl};
-use tauri::{
-    GlobalShortcutManager, Manager, PhysicalPosition, PhysicalSize, Window, WindowEvent, Wry
-};
-use super::panel::{create_spotlight_panel, RawNSPanel};
-use crate::{PluginConfig, WindowConfig};
+use tauri::{Runtime, Window};
+use super::panel::{create_spotlight_panel, spotlight_panel};
+use crate::{MonitorSelection, Position, WindowConfig};
 use crate::Error;
+use crate::manager::Platform;
+use crate::saved_geometry::monitor_key;
+use crate::geometry::{self, Point, Rect, Screen, Size};
+
+/// Spotlight windows as non-activating `NSPanel`s, in the points of `flip_rect`.
+pub(crate) struct MacosPlatform;
+
+impl Platform for MacosPlatform {
+    /// Turns the window into a spotlight panel.
+    ///
+    /// The window stays an NSPanel after being unregistered.
+    fn init_window<R: Runtime>(window: &Window<R>, window_config: &WindowConfig) -> Result<(), Error> {
+        create_spotlight_panel(window)?;
+        set_window_level(window, window_config)
+    }
 
-#[link(name = "Foundation", kind = "framework")]
-extern "C" {
-    pub fn NSMouseInRect(aPoint: NSPoint, aRect: NSRect, flipped: BOOL) -> BOOL;
-}
+    fn show_window<R: Runtime>(window: &Window<R>) -> Result<(), Error> {
+        spotlight_panel(window)?.show();
+        Ok(())
+    }
 
-#[derive(Default, Debug)]
-pub struct SpotlightManager {
-    pub config: PluginConfig,
-    registered_panels: RwLock<HashMap<String, Mutex<ShareId<RawNSPanel>>>>,
-}
+    fn hide_window<R: Runtime>(window: &Window<R>) -> Result<(), Error> {
+        spotlight_panel(window)?.order_out(None);
+        Ok(())
+    }
 
-impl SpotlightManager {
-    pub fn new(config: PluginConfig) -> Self {
-        let mut manager = Self::default();
-        manager.config = config;
-        manager
+    fn frame<R: Runtime>(window: &Window<R>) -> Result<Rect, Error> {
+        let handle: id = window.ns_window().map_err(|_| Error::FailedToGetNSWindow)? as _;
+        Ok(flip_rect(unsafe { handle.frame() }))
     }
 
-    fn get_window_config(&self, window: &Window<Wry>) -> Option<WindowConfig> {
-        if let Some(window_configs) = self.config.windows.clone() {
-            for window_config in window_configs {
-                if window.label() == window_config.label {
-                    return Some(window_config.clone());
-                }
-            }
-        }
-        None
+    fn monitor_at<R: Runtime>(_window: &Window<R>, point: Point) -> Result<Option<(String, Rect)>, Error> {
+        Ok(get_monitors()
+            .into_iter()
+            .find(|monitor| monitor.frame.contains(point))
+            .map(|monitor| (monitor_key(monitor.name.as_deref(), monitor.frame), monitor.frame)))
     }
 
-    pub fn init_spotlight_window(&self, window: &Window<Wry>) -> Result<(), Error> {
-        let window_config = match self.get_window_config(&window) {
-            Some(window_config) => window_config,
+    /// Positions a given window on the monitor picked by `monitor_selection`, within the part
+    /// of the screen not covered by the menu bar and the Dock
+    ///
+    /// `saved_frames` holds the remembered geometry of the window, which takes precedence over
+    /// `position` on the screens it was recorded on.
+    fn position_window<R: Runtime>(
+        window: &Window<R>,
+        position: &Position,
+        monitor_selection: &MonitorSelection,
+        last_position: Option<Point>,
+        saved_frames: Option<&HashMap<String, Rect>>,
+    ) -> Result<(), Error> {
+        let monitors = get_monitors();
+        let screens: Vec<Screen> = monitors
+            .iter()
+            .enumerate()
+            .map(|(index, monitor)| Screen {
+                name: monitor.name.as_deref(),
+                frame: monitor.frame,
+                // the first screen holds the menu bar
+                primary: index == 0,
+            })
+            .collect();
+        let mouse_location: NSPoint = unsafe { msg_send![class!(NSEvent), mouseLocation] };
+        let cursor = Point { x: mouse_location.x, y: -mouse_location.y };
+        let focused_window = focused_window_rect(window)?;
+        let monitor = match geometry::select_screen(monitor_selection, &screens, Some(cursor), focused_window) {
+            Some(index) => &monitors[index],
             None => return Ok(()),
         };
-        let label = window.label();
-        let mut map = self.registered_panels.write().map_err(|_| Error::RwLock(String::from("failed to write registered panels")))?;
-        if map.get(label).is_none() {
-            map.insert(String::from(label), Mutex::new(create_spotlight_panel(window)));
-            register_shortcut_for_window(&window, &window_config)?;
-            register_close_shortcut(&window)?;
-            handle_focus_state_change(&window);
-            set_window_level(&window, &window_config)?;
-        }
-        Ok(())
-    }
-
-    pub fn show(&self, window: &Window<Wry>) -> Result<(), Error> {
-        position_window_at_the_center_of_the_monitor_with_cursor(&window)?;
-        let label = window.label();
-        let map = self.registered_panels.read().map_err(|_| Error::RwLock(String::from("failed to read registered panels")))?;
-        if let Some(panel) = map.get(label) {
-            let panel = panel.lock().map_err(|_| Error::Mutex(String::from("failed to lock panel")))?;
-            panel.show();
-        }
+        let handle: id = window.ns_window().map_err(|_| Error::FailedToGetNSWindow)? as _;
+        let win_frame: NSRect = unsafe { handle.frame() };
+        let saved_frame = saved_frames.and_then(|frames| frames.get(&monitor_key(monitor.name.as_deref(), monitor.frame)));
+        let (origin, size) = match saved_frame {
+            Some(saved_frame) => {
+                let frame = geometry::restore(*saved_frame, monitor.frame, monitor.visible_frame);
+                (Point { x: frame.x, y: frame.y }, NSSize::new(frame.width, frame.height))
+            }
+            None => {
+                let size = Size { width: win_frame.size.width, height: win_frame.size.height };
+                // Cocoa works in points, so offsets need no scaling
+                let origin = geometry::place(position, monitor.visible_frame, size, 1.0, Some(cursor), last_position);
+                (origin, win_frame.size)
+            }
+        };
+        let frame = flip_rect(NSRect::new(NSPoint::new(origin.x, origin.y), size));
+        let rect = NSRect {
+            origin: NSPoint { x: frame.x, y: frame.y },
+            size,
+        };
+        let _: () = unsafe { msg_send![handle, setFrame: rect display: YES] };
         Ok(())
     }
 
-    pub fn hide(&self, window: &Window<Wry>) -> Result<(), Error> {
-        let label = window.label();
-        let map = self.registered_panels.read().map_err(|_| Error::RwLock(String::from("failed to read registered panels")))?;
-        if let Some(panel) = map.get(label) {
-            let panel = panel.lock().map_err(|_| Error::Mutex(String::from("failed to lock panel")))?;
-            panel.order_out(None);
-        }
+    /// Resizes the panel to `height` points, keeping its top edge in place and the panel
+    /// clear of the menu bar and the Dock.
+    fn set_content_height<R: Runtime>(
+        window: &Window<R>,
+        height: f64,
+        min_height: Option<f64>,
+        max_height: Option<f64>,
+    ) -> Result<(), Error> {
+        let handle: id = window.ns_window().map_err(|_| Error::FailedToGetNSWindow)? as _;
+        let win_frame: NSRect = unsafe { handle.frame() };
+        let content_rect: NSRect = unsafe { msg_send![handle, contentRectForFrameRect: win_frame] };
+        // the frame includes the title bar, if any
+        let decorations = win_frame.size.height - content_rect.size.height;
+        let screen: id = unsafe { msg_send![handle, screen] };
+        let area = if screen == nil {
+            None
+        } else {
+            let visible_frame: NSRect = unsafe { msg_send![screen, visibleFrame] };
+            Some(flip_rect(visible_frame))
+        };
+        let frame = geometry::fit_height(
+            flip_rect(win_frame),
+            height + decorations,
+            min_height.map(|min_height| min_height + decorations),
+            max_height.map(|max_height| max_height + decorations),
+            area,
+        );
+        let frame = flip_rect(NSRect::new(NSPoint::new(frame.x, frame.y), NSSize::new(frame.width, frame.height)));
+        let rect = NSRect {
+            origin: NSPoint { x: frame.x, y: frame.y },
+            size: NSSize::new(frame.width, frame.height),
+        };
+        let _: () = unsafe { msg_send![handle, setFrame: rect display: YES] };
         Ok(())
     }
 }
 
-fn set_window_level(window: &Window<Wry>, window_config: &WindowConfig) -> Result<(), Error> {
+fn set_window_level<R: Runtime>(window: &Window<R>, window_config: &WindowConfig) -> Result<(), Error> {
     if let Some(level) = window_config.macos_window_level {
         let handle: id = window.ns_window().map_err(|_| Error::FailedToGetNSWindow)? as _;
         unsafe { handle.setLevel_((level).into()) };
@@ -107,149 +164,60 @@ macro_rules! nsstring_to_string {
     }};
 }
 
-fn register_shortcut_for_window(window: &Window<Wry>, window_config: &WindowConfig) -> Result<(), Error> {
-    let window = window.to_owned();
-    let mut shortcut_manager = window.app_handle().global_shortcut_manager();
-    shortcut_manager.register(&window_config.shortcut, move || {
-        let app_handle = window.app_handle();
-        let manager = app_handle.state::<SpotlightManager>();
-        if window.is_visible().unwrap() {
-            manager.hide(&window).unwrap();
-        } else {
-            manager.show(&window).unwrap();
-        }
-    }).map_err(|_| Error::Other(String::from("failed to register shortcut")))?;
-    Ok(())
-}
-
-fn register_close_shortcut(window: &Window<Wry>) -> Result<(), Error> {
-    let window = window.to_owned();
-    let mut shortcut_manager = window.app_handle().global_shortcut_manager();
-    let app_handle = window.app_handle();
-    let manager = app_handle.state::<SpotlightManager>();
-    if let Some(close_shortcut) = &manager.config.global_close_shortcut {
-        if let Ok(registered) = shortcut_manager.is_registered(&close_shortcut) {
-            if !registered {
-                shortcut_manager.register(&close_shortcut, move || {
-                    let app_handle = window.app_handle();
-                    let state = app_handle.state::<SpotlightManager>();
-                    let labels = if let Some(ref windows) = state.config.windows {
-                        windows.iter().map(|window| window.label.clone()).collect()
-                    } else {
-                        vec![]
-                    };
-                    for label in labels {
-                        if let Some(window) = app_handle.get_window(&label) {
-                            state.hide(&window).unwrap();
-                        }
-                    }
-                }).map_err(tauri::Error::Runtime)?;
-            }
-        } else {
-            return Err(Error::Other(String::from("failed to register shortcut")));
-        }
-    }
-    Ok(())
-}
-
-fn unregister_close_shortcut(window: &Window<Wry>) -> Result<(), Error> {
-    let window = window.to_owned();
-    let mut shortcut_manager = window.app_handle().global_shortcut_manager();
-    let app_handle = window.app_handle();
-    let manager = app_handle.state::<SpotlightManager>();
-    if let Some(close_shortcut) = manager.config.global_close_shortcut.clone() {
-        if let Ok(registered) = shortcut_manager.is_registered(&close_shortcut) {
-            if registered {
-                shortcut_manager.unregister(&close_shortcut).map_err(tauri::Error::Runtime)?;
-            }
-        } else {
-            return Err(Error::Other(String::from("failed to register shortcut")));
-        }
+/// Converts a rectangle between Cocoa coordinates, where `y` grows upwards, and the
+/// top-left based coordinates used by the geometry module. The conversion is its own inverse.
+fn flip_rect(rect: NSRect) -> Rect {
+    Rect {
+        x: rect.origin.x,
+        y: -(rect.origin.y + rect.size.height),
+        width: rect.size.width,
+        height: rect.size.height,
     }
-    Ok(())
 }
 
-fn handle_focus_state_change(window: &Window<Wry>) {
-    let w = window.to_owned();
-    window.on_window_event(move |event| {
-        if let WindowEvent::Focused(false) = event {
-            unregister_close_shortcut(&w).unwrap(); // FIXME:
-            w.hide().unwrap();
-        } else {
-            register_close_shortcut(&w).unwrap(); // FIXME:
-        }
-    });
-}
-
-/// Positions a given window at the center of the monitor with cursor
-fn position_window_at_the_center_of_the_monitor_with_cursor(window: &Window<Wry>) -> Result<(), Error> {
-    if let Some(monitor) = get_monitor_with_cursor() {
-        let display_size = monitor.size.to_logical::<f64>(monitor.scale_factor);
-        let display_pos = monitor.position.to_logical::<f64>(monitor.scale_factor);
-        let handle: id = window.ns_window().map_err(|_| Error::FailedToGetNSWindow)? as _;
-        let win_frame: NSRect = unsafe { handle.frame() };
-        let rect = NSRect {
-            origin: NSPoint {
-                x: (display_pos.x + (display_size.width / 2.0)) - (win_frame.size.width / 2.0),
-                y: (display_pos.y + (display_size.height / 2.0)) - (win_frame.size.height / 2.0),
-            },
-            size: win_frame.size,
-        };
-        let _: () = unsafe { msg_send![handle, setFrame: rect display: YES] };
-    }
-    Ok(())
+/// Returns the frame of the focused window of the app, other than `window`, see `flip_rect`.
+fn focused_window_rect<R: Runtime>(window: &Window<R>) -> Result<Option<Rect>, Error> {
+    let focused = window
+        .app_handle()
+        .windows()
+        .into_values()
+        .find(|other| other.label() != window.label() && other.is_focused().unwrap_or(false));
+    let focused = match focused {
+        Some(focused) => focused,
+        None => return Ok(None),
+    };
+    let handle: id = focused.ns_window().map_err(|_| Error::FailedToGetNSWindow)? as _;
+    Ok(Some(flip_rect(unsafe { handle.frame() })))
 }
 
+/// A screen in the coordinates of `flip_rect`, in points.
 struct Monitor {
-    #[allow(dead_code)]
-    pub name: Option<String>,
-    pub size: PhysicalSize<u32>,
-    pub position: PhysicalPosition<i32>,
-    pub scale_factor: f64,
+    name: Option<String>,
+    frame: Rect,
+    /// The frame without the menu bar and the Dock
+    visible_frame: Rect,
 }
 
-/// Returns the Monitor with cursor
-fn get_monitor_with_cursor() -> Option<Monitor> {
+/// Returns the screens, the primary one first.
+fn get_monitors() -> Vec<Monitor> {
     objc::rc::autoreleasepool(|| {
-        let mouse_location: NSPoint = unsafe { msg_send![class!(NSEvent), mouseLocation] };
         let screens: id = unsafe { msg_send![class!(NSScreen), screens] };
         let screens_iter: id = unsafe { msg_send![screens, objectEnumerator] };
-        let mut next_screen: id;
-
-        let frame_with_cursor: Option<NSRect> = loop {
-            next_screen = unsafe { msg_send![screens_iter, nextObject] };
-            if next_screen == nil {
-                break None;
-            }
-
-            let frame: NSRect = unsafe { msg_send![next_screen, frame] };
-            let is_mouse_in_screen_frame: BOOL =
-                unsafe { NSMouseInRect(mouse_location, frame, NO) };
-            if is_mouse_in_screen_frame == YES {
-                break Some(frame);
+        let mut monitors = Vec::new();
+        loop {
+            let screen: id = unsafe { msg_send![screens_iter, nextObject] };
+            if screen == nil {
+                break;
             }
-        };
-
-        if let Some(frame) = frame_with_cursor {
-            let name: id = unsafe { msg_send![next_screen, localizedName] };
-            let screen_name = unsafe { nsstring_to_string!(name) };
-            let scale_factor: CGFloat = unsafe { msg_send![next_screen, backingScaleFactor] };
-            let scale_factor: f64 = scale_factor;
-
-            return Some(Monitor {
-                name: screen_name,
-                position: PhysicalPosition {
-                    x: (frame.origin.x * scale_factor) as i32,
-                    y: (frame.origin.y * scale_factor) as i32,
-                },
-                size: PhysicalSize {
-                    width: (frame.size.width * scale_factor) as u32,
-                    height: (frame.size.height * scale_factor) as u32,
-                },
-                scale_factor,
+            let name: id = unsafe { msg_send![screen, localizedName] };
+            let frame: NSRect = unsafe { msg_send![screen, frame] };
+            let visible_frame: NSRect = unsafe { msg_send![screen, visibleFrame] };
+            monitors.push(Monitor {
+                name: unsafe { nsstring_to_string!(name) },
+                frame: flip_rect(frame),
+                visible_frame: flip_rect(visible_frame),
             });
         }
-
-        None
+        monitors
     })
 }
@@ -0,0 +1,32 @@
+use std::{io::{BufRead, BufReader}, process::{Child, Command, Stdio}};
+use gio::{Cancellable, DBusConnection, DBusConnectionFlags};
+
+/// A message bus of its own, killed when the test ends.
+pub(crate) struct PrivateBus {
+    daemon: Child,
+    address: String,
+}
+
+impl PrivateBus {
+    pub(crate) fn spawn() -> Self {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("dbus-daemon is required to run this test");
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+        Self { daemon, address: String::from(address.trim()) }
+    }
+
+    pub(crate) fn connect(&self) -> DBusConnection {
+        let flags = DBusConnectionFlags::AUTHENTICATION_CLIENT | DBusConnectionFlags::MESSAGE_BUS_CONNECTION;
+        DBusConnection::for_address_sync(&self.address, flags, None, None::<&Cancellable>).unwrap()
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+    }
+}
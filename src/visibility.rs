@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+/// Visibility of a spotlight window, tracked by the `SpotlightManager` so that
+/// shortcuts, commands and window events agree on whether a window is shown.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    Hidden,
+    /// A show was requested and the window is being shown
+    Showing,
+    Visible,
+    /// A hide was requested and the window is being hidden
+    Hiding,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Visibility::Hidden
+    }
+}
+
+/// A request to change the visibility of a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Request {
+    Show,
+    Hide,
+    Toggle,
+}
+
+impl Visibility {
+    pub(crate) fn from_visible(visible: bool) -> Self {
+        if visible {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        }
+    }
+
+    /// The name of the state, as serialized.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Visibility::Hidden => "hidden",
+            Visibility::Showing => "showing",
+            Visibility::Visible => "visible",
+            Visibility::Hiding => "hiding",
+        }
+    }
+
+    /// Whether the window is visible or on its way to being visible.
+    pub fn is_visible(self) -> bool {
+        matches!(self, Visibility::Showing | Visibility::Visible)
+    }
+
+    /// Returns the transitional state `request` moves the window into, or `None` if the
+    /// window already is where it was asked to be, or if another transition is in progress.
+    ///
+    /// A toggle is decided on the tracked state only, so a shortcut pressed while the
+    /// window is being hidden does not show it again.
+    pub(crate) fn begin(self, request: Request) -> Option<Self> {
+        match (self, request) {
+            (Visibility::Hidden, Request::Show | Request::Toggle) => Some(Visibility::Showing),
+            (Visibility::Visible, Request::Hide | Request::Toggle) => Some(Visibility::Hiding),
+            _ => None,
+        }
+    }
+
+    /// The state reached once the transition succeeded.
+    pub(crate) fn finish(self) -> Self {
+        match self {
+            Visibility::Showing => Visibility::Visible,
+            Visibility::Hiding => Visibility::Hidden,
+            visibility => visibility,
+        }
+    }
+
+    /// The state to go back to when the transition failed.
+    pub(crate) fn revert(self) -> Self {
+        match self {
+            Visibility::Showing => Visibility::Hidden,
+            Visibility::Hiding => Visibility::Visible,
+            visibility => visibility,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Request, Visibility};
+
+    #[test]
+    fn show_and_hide() {
+        let showing = Visibility::Hidden.begin(Request::Show).unwrap();
+        assert_eq!(showing, Visibility::Showing);
+        assert_eq!(showing.finish(), Visibility::Visible);
+        let hiding = Visibility::Visible.begin(Request::Hide).unwrap();
+        assert_eq!(hiding, Visibility::Hiding);
+        assert_eq!(hiding.finish(), Visibility::Hidden);
+    }
+
+    #[test]
+    fn toggle() {
+        assert_eq!(Visibility::Hidden.begin(Request::Toggle), Some(Visibility::Showing));
+        assert_eq!(Visibility::Visible.begin(Request::Toggle), Some(Visibility::Hiding));
+    }
+
+    #[test]
+    fn ignore_requests_without_effect() {
+        assert_eq!(Visibility::Hidden.begin(Request::Hide), None);
+        assert_eq!(Visibility::Visible.begin(Request::Show), None);
+    }
+
+    #[test]
+    fn ignore_requests_during_transition() {
+        for request in [Request::Show, Request::Hide, Request::Toggle] {
+            assert_eq!(Visibility::Showing.begin(request), None);
+            assert_eq!(Visibility::Hiding.begin(request), None);
+        }
+    }
+
+    #[test]
+    fn revert_failed_transition() {
+        assert_eq!(Visibility::Showing.revert(), Visibility::Hidden);
+        assert_eq!(Visibility::Hiding.revert(), Visibility::Visible);
+        assert_eq!(Visibility::Visible.revert(), Visibility::Visible);
+    }
+
+    #[test]
+    fn names_match_serialization() {
+        for visibility in [Visibility::Hidden, Visibility::Showing, Visibility::Visible, Visibility::Hiding] {
+            assert_eq!(serde_json::to_value(visibility).unwrap(), visibility.as_str());
+        }
+    }
+}
@@ -0,0 +1,18 @@
+(function () {
+  if (typeof window.__TAURI_INVOKE__ !== 'function') {
+    return
+  }
+  function acknowledge (sequence) {
+    window.__TAURI_INVOKE__('plugin:spotlight|will_show_handled', { sequence: sequence }).catch(function () {})
+  }
+  // Evaluated by the plugin right after emitting `spotlight://will-show`, with the number of
+  // the show to acknowledge. The timeout lets the listeners of the event and the updates they
+  // trigger run first.
+  window.__SPOTLIGHT_WILL_SHOW__ = function (sequence) {
+    setTimeout(function () {
+      acknowledge(sequence)
+    })
+  }
+  // Tells the plugin to wait for this webview before showing the window
+  acknowledge(null)
+})()